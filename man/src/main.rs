@@ -36,11 +36,26 @@ fn main() {
         .long("add-remote")
         .help("Add remote to project.")
     )
+    .option(
+      Opt::new("<SUBCOMMAND>")
+        .long("config")
+        .help("Allows working with the config itself. config migrate upgrades it (including the legacy ~/.fw.json) to the current schema version after backing it up, --dry-run only prints the migrations. config sync commits the changed projects and tags of the config dir (never settings.toml), pulls from its git remote origin and pushes.")
+    )
+    .option(
+      Opt::new("")
+        .long("doctor")
+        .help("Check the config and the workspace: undefined tags, projects and tags defined more than once, missing override paths, projects sharing a path, remotes that differ from the config, the shell and ssh setup. Exits non-zero if there are problems.")
+    )
     .option(
       Opt::new("<CMD>")
         .long("foreach")
         .help("Run script on each project.")
     )
+    .option(
+      Opt::new("<NAME>")
+        .long("fork")
+        .help("Fork project on github (into --organization ORG or your account). The fork becomes the git url of the project, the original repository the upstream remote.")
+    )
     .option(
       Opt::new("<OPTIONS>")
         .long("gen-reworkon")
@@ -76,10 +91,15 @@ fn main() {
         .long("ls")
         .help("List projects.")
     )
+    .option(
+      Opt::new("<NAME>")
+        .long("new")
+        .help("Create a new repository on github (in --organization ORG or your account, --private to hide it) and add it as project. The project is initialized in the workspace and the after clone hooks of its tags run.")
+    )
     .option(
       Opt::new("<ORG_NAME>")
         .long("org-import")
-        .help("Import all repositories from github org into fw. Token can be set in the settings file or provided via the environment variable FW_GITHUB_TOKEN.")
+        .help("Import all repositories from a github org into fw. Use --provider gitlab, gitea, forgejo, bitbucket or bitbucket-server for other hosting services. --user NAME (with --forks and --starred) and --team ORG/TEAM import the repositories of a github user or team instead. --topic, --language and --visibility filter the imported repositories. --https uses https instead of ssh clone urls. github_api_url and github_ssh_host in the settings file select a GitHub Enterprise instance. --reconcile reports new, renamed, archived and deleted repositories of an earlier import; --update-urls, --tag-archived and --remove-deleted fix them. Base urls and tokens can be set in the settings file, tokens also via the environment variables FW_GITHUB_TOKEN, FW_GITLAB_TOKEN, FW_GITEA_TOKEN and FW_BITBUCKET_TOKEN. The github token can also be printed by github_token_command or read from a file with github_token = \"file:<path>\".")
     )
     .option(
       Opt::new("<OPTIONS>")
@@ -106,6 +126,11 @@ fn main() {
         .long("projectile")
         .help("Write projectile bookmarks.")
     )
+    .option(
+      Opt::new("<OPTIONS>")
+        .long("prs")
+        .help("Show the open github pull requests of all projects grouped by project. --filter mine or review-requested shows only yours or the ones waiting for your review, --tag filters projects and --json prints JSON.")
+    )
     .option(
       Opt::new("<NAME>")
         .long("remove")
@@ -114,7 +139,7 @@ fn main() {
     .option(
       Opt::new("<NAME> <REMOTE_NAME>")
         .long("remove-remote")
-        .help("Removes remote from project (Only in the fw configuration. An existing remote will not be deleted by sync).")
+        .help("Removes remote from project (Only in the fw configuration. An existing remote will only be deleted by sync --prune).")
     )
     .option(
       Opt::new("")
@@ -126,6 +151,11 @@ fn main() {
         .long("setup")
         .help("Setup config from existing workspace.")
     )
+    .option(
+      Opt::new("<OPTIONS>")
+        .long("status")
+        .help("Show git status (branch, ahead/behind, dirty files, stashes) of all projects.")
+    )
     .option(
       Opt::new("<OPTIONS>")
         .long("sync")
@@ -149,8 +179,7 @@ fn main() {
       Section::new("What this is, and isn't")
         .paragraph("fw is a tool I wrote to do my bidding. It might not work for you if your workflow differs a lot from mine or might require adjustments. Here are the assumptions:")
         .paragraph("* only git repositories")
-        .paragraph("* only ssh clone (easily resolveable by putting more work in the git2 bindings usage")
-        .paragraph("* ssh-agent based authentication")
+        .paragraph("* authentication via ssh-agent, ssh_key_files, git credential helpers or the github_token for HTTPS remotes on github.com")
     )
     .custom(
       Section::new("If you can live with all of the above, you get:")
@@ -173,7 +202,7 @@ fn main() {
     .custom(
       Section::new("Turn fw configuration into reality")
         .paragraph("From now on you can")
-        .paragraph("$  fw sync # Make sure your ssh agent has your key otherwise this command will just hang because it waits for your password (you can't enter it!).")
+        .paragraph("$  fw sync")
         .paragraph("which will clone all missing projects that are described by the configuration but not present in your workspace. Existing projects will be synced with the remote. That means a fast-forward is executed if possible.")
     )
     .custom(
@@ -42,6 +42,15 @@ For further information please have a look at our README https://github.com/broc
 						.action(ArgAction::SetTrue)
 						.num_args(0),
 				)
+				.arg(
+					Arg::new("update-strategy")
+						.long("update-strategy")
+						.value_parser(["ff", "rebase", "autostash-rebase", "fetch-only"])
+						.help("How the checked out branch is updated. Overrides the update_strategy of the projects (default: ff)")
+						.num_args(1)
+						.conflicts_with("no-fast-forward-merge")
+						.required(false),
+				)
 				.arg(
 					Arg::new("only-new")
 						.long("only-new")
@@ -58,6 +67,47 @@ For further information please have a look at our README https://github.com/broc
 						.value_parser(clap::builder::RangedI64ValueParser::<i32>::new().range(0..=128))
 						.help("Sets the count of worker")
 						.num_args(1),
+				)
+				.arg(
+					Arg::new("report")
+						.long("report")
+						.value_parser(["json", "junit"])
+						.help("Print a per project report (json or junit) on stdout after syncing")
+						.num_args(1)
+						.required(false),
+				)
+				.arg(
+					Arg::new("prune")
+						.long("prune")
+						.help("Deletes git remotes that are not in the fw config and reports repositories in the workspace that are not tracked by fw")
+						.action(ArgAction::SetTrue)
+						.num_args(0),
+				)
+				.arg(
+					Arg::new("import-untracked")
+						.long("import-untracked")
+						.help("Imports untracked repositories found by --prune into the fw config")
+						.requires("prune")
+						.conflicts_with("delete-untracked")
+						.action(ArgAction::SetTrue)
+						.num_args(0),
+				)
+				.arg(
+					Arg::new("delete-untracked")
+						.long("delete-untracked")
+						.help("Deletes untracked repositories found by --prune from disk. Repositories with uncommitted changes, stashes or unpushed commits are skipped.")
+						.requires("prune")
+						.action(ArgAction::SetTrue)
+						.num_args(0),
+				)
+				.arg(
+					Arg::new("yes")
+						.long("yes")
+						.short('y')
+						.help("Deletes untracked repositories without asking for confirmation")
+						.requires("delete-untracked")
+						.action(ArgAction::SetTrue)
+						.num_args(0),
 				),
 		)
 		.subcommand(
@@ -96,7 +146,22 @@ For further information please have a look at our README https://github.com/broc
 		.subcommand(
 			Command::new("org-import")
 				.about(
-					"Import all repositories from github org into fw. Token can be set in the settings file or provided via the environment variable FW_GITHUB_TOKEN",
+					"Import all repositories from a github org (or gitlab group, gitea/forgejo org, bitbucket workspace or bitbucket server project) into fw. Base urls and tokens can be set in the settings file, tokens also via the environment variables FW_GITHUB_TOKEN, FW_GITLAB_TOKEN, FW_GITEA_TOKEN and FW_BITBUCKET_TOKEN",
+				)
+				.arg(
+					Arg::new("provider")
+						.long("provider")
+						.value_parser(["github", "gitlab", "gitea", "forgejo", "bitbucket", "bitbucket-server"])
+						.default_value("github")
+						.help("The git hosting service to import from")
+						.num_args(1),
+				)
+				.arg(
+					Arg::new("https")
+						.long("https")
+						.help("Use https instead of ssh clone urls")
+						.num_args(0)
+						.action(ArgAction::SetTrue),
 				)
 				.arg(
 					Arg::new("include-archived")
@@ -107,7 +172,101 @@ For further information please have a look at our README https://github.com/broc
 						.action(ArgAction::SetTrue)
 						.required(false),
 				)
-				.arg(Arg::new("ORG_NAME").value_name("ORG_NAME").index(1).required(true)),
+				.arg(
+					Arg::new("user")
+						.long("user")
+						.value_name("NAME")
+						.help("Import the repositories owned by a github user instead of an org")
+						.num_args(1)
+						.required(false),
+				)
+				.arg(
+					Arg::new("forks")
+						.long("forks")
+						.help("Also import the forks of the user")
+						.num_args(0)
+						.action(ArgAction::SetTrue)
+						.requires("user")
+						.conflicts_with_all(["ORG_NAME", "team"]),
+				)
+				.arg(
+					Arg::new("starred")
+						.long("starred")
+						.help("Also import the repositories starred by the user")
+						.num_args(0)
+						.action(ArgAction::SetTrue)
+						.requires("user")
+						.conflicts_with_all(["ORG_NAME", "team"]),
+				)
+				.arg(
+					Arg::new("team")
+						.long("team")
+						.value_name("ORG/TEAM")
+						.help("Import the repositories of a github team instead of an org")
+						.num_args(1)
+						.conflicts_with("user")
+						.required(false),
+				)
+				.arg(
+					Arg::new("topic")
+						.long("topic")
+						.help("Only import repositories with this topic. More than 1 is allowed.")
+						.num_args(1)
+						.action(ArgAction::Append),
+				)
+				.arg(
+					Arg::new("language")
+						.long("language")
+						.help("Only import repositories with this primary language. More than 1 is allowed.")
+						.num_args(1)
+						.action(ArgAction::Append),
+				)
+				.arg(
+					Arg::new("visibility")
+						.long("visibility")
+						.value_parser(["public", "private", "internal"])
+						.help("Only import repositories with this visibility")
+						.num_args(1),
+				)
+				.arg(
+					Arg::new("reconcile")
+						.long("reconcile")
+						.help("Compare the already imported projects with the repositories upstream and report new, renamed, archived and deleted ones")
+						.num_args(0)
+						.action(ArgAction::SetTrue)
+						.conflicts_with_all(["topic", "language", "visibility", "include-archived"]),
+				)
+				.arg(
+					Arg::new("update-urls")
+						.long("update-urls")
+						.help("With --reconcile: update the git url of renamed or moved repositories")
+						.num_args(0)
+						.action(ArgAction::SetTrue)
+						.requires("reconcile"),
+				)
+				.arg(
+					Arg::new("tag-archived")
+						.long("tag-archived")
+						.help("With --reconcile: tag projects of archived repositories with 'archived'")
+						.num_args(0)
+						.action(ArgAction::SetTrue)
+						.requires("reconcile"),
+				)
+				.arg(
+					Arg::new("remove-deleted")
+						.long("remove-deleted")
+						.help("With --reconcile: remove projects of deleted repositories from the fw config")
+						.num_args(0)
+						.action(ArgAction::SetTrue)
+						.requires("reconcile"),
+				)
+				.arg(
+					Arg::new("ORG_NAME")
+						.value_name("ORG_NAME")
+						.index(1)
+						.required_unless_present_any(["user", "team"])
+						.conflicts_with_all(["user", "team"]),
+				),
 		)
 		.subcommand(
 			Command::new("add-remote")
@@ -118,10 +277,51 @@ For further information please have a look at our README https://github.com/broc
 		)
 		.subcommand(
 			Command::new("remove-remote")
-				.about("Removes remote from project (Only in the fw configuration. An existing remote will not be deleted by a sync)")
+				.about("Removes remote from project (Only in the fw configuration. An existing remote will only be deleted by a sync with --prune)")
 				.arg(Arg::new("NAME").value_name("NAME").index(1).required(true))
 				.arg(Arg::new("REMOTE_NAME").value_name("REMOTE_NAME").index(2).required(true)),
 		)
+		.subcommand(
+			Command::new("new")
+				.about("Create a new repository on github and add it as project initialized in the workspace")
+				.arg(Arg::new("NAME").value_name("NAME").index(1).required(true))
+				.arg(
+					Arg::new("organization")
+						.long("organization")
+						.value_name("ORG")
+						.help("Create the repository in this organization instead of your account")
+						.num_args(1),
+				)
+				.arg(
+					Arg::new("private")
+						.long("private")
+						.help("Create a private repository")
+						.num_args(0)
+						.action(ArgAction::SetTrue),
+				)
+				.arg(Arg::new("description").long("description").value_name("DESCRIPTION").num_args(1))
+				.arg(
+					Arg::new("tag")
+						.long("tag")
+						.short('t')
+						.value_name("tag")
+						.num_args(1)
+						.action(ArgAction::Append)
+						.required(false),
+				),
+		)
+		.subcommand(
+			Command::new("fork")
+				.about("Fork project on github, use the fork as origin and the original repository as upstream remote")
+				.arg(Arg::new("NAME").value_name("NAME").index(1).required(true))
+				.arg(
+					Arg::new("organization")
+						.long("organization")
+						.value_name("ORG")
+						.help("Fork into this organization instead of your account")
+						.num_args(1),
+				),
+		)
 		.subcommand(
 			Command::new("add")
 				.about("Add project to config")
@@ -157,7 +357,15 @@ For further information please have a look at our README https://github.com/broc
 						.num_args(1)
 						.required(false),
 				)
-				.arg(Arg::new("trusted").long("trusted").num_args(0).required(false).action(ArgAction::SetTrue)),
+				.arg(Arg::new("trusted").long("trusted").num_args(0).required(false).action(ArgAction::SetTrue))
+				.arg(
+					Arg::new("branch")
+						.value_name("branch")
+						.long("branch")
+						.help("Branch to check out on clone and to keep up to date on sync")
+						.num_args(1)
+						.required(false),
+				),
 		)
 		.subcommand(
 			Command::new("remove")
@@ -202,6 +410,69 @@ For further information please have a look at our README https://github.com/broc
 						.action(ArgAction::Append),
 				),
 		)
+		.subcommand(
+			Command::new("status")
+				.about("Show git status (branch, ahead/behind, dirty files, stashes) of all projects")
+				.arg(
+					Arg::new("tag")
+						.long("tag")
+						.short('t')
+						.help("Filter projects by tag. More than 1 is allowed.")
+						.required(false)
+						.num_args(1)
+						.action(ArgAction::Append),
+				)
+				.arg(
+					Arg::new("parallel")
+						.short('p')
+						.help("Parallelism to use (default is set by rayon but probably equal to the number of cores)")
+						.required(false)
+						.value_parser(clap::builder::RangedI64ValueParser::<i32>::new().range(0..=128))
+						.num_args(1),
+				)
+				.arg(
+					Arg::new("json")
+						.help("output json instead of a table")
+						.short('j')
+						.long("json")
+						.action(ArgAction::SetTrue)
+						.num_args(0)
+						.required(false),
+				),
+		)
+		.subcommand(Command::new("doctor").about(
+			"Check the config and the workspace for problems (undefined tags, duplicates, paths, remotes, shell, ssh) and exit non-zero if there are any",
+		))
+		.subcommand(
+			Command::new("prs")
+				.about("Show open github pull requests of all projects")
+				.arg(
+					Arg::new("tag")
+						.long("tag")
+						.short('t')
+						.help("Filter projects by tag. More than 1 is allowed.")
+						.required(false)
+						.num_args(1)
+						.action(ArgAction::Append),
+				)
+				.arg(
+					Arg::new("filter")
+						.long("filter")
+						.value_parser(["all", "mine", "review-requested"])
+						.default_value("all")
+						.help("Only pull requests authored by you or requesting your review")
+						.num_args(1),
+				)
+				.arg(
+					Arg::new("json")
+						.help("output json instead of a list")
+						.short('j')
+						.long("json")
+						.action(ArgAction::SetTrue)
+						.num_args(0)
+						.required(false),
+				),
+		)
 		.subcommand(
 			Command::new("print-path")
 				.about("Print project path on stdout")
@@ -262,6 +533,14 @@ For further information please have a look at our README https://github.com/broc
 				.about("Modifies project settings.")
 				.arg(Arg::new("NAME").value_name("NAME").required(true))
 				.arg(Arg::new("git").value_name("URL").long("git-url").num_args(1).required(false))
+				.arg(
+					Arg::new("branch")
+						.value_name("branch")
+						.long("branch")
+						.help("Branch to check out on clone and to keep up to date on sync")
+						.num_args(1)
+						.required(false),
+				)
 				.arg(
 					Arg::new("override-path")
 						.value_name("override-path")
@@ -353,6 +632,7 @@ For further information please have a look at our README https://github.com/broc
 								.required(false),
 						)
 						.arg(Arg::new("workspace").value_name("workspace").long("workspace").num_args(1).required(false))
+						.arg(Arg::new("branch").value_name("branch").long("branch").num_args(1).required(false))
 						.arg(
 							Arg::new("after-clone")
 								.value_name("after-clone")
@@ -362,4 +642,23 @@ For further information please have a look at our README https://github.com/broc
 						),
 				),
 		)
+		.subcommand(
+			Command::new("config")
+				.about("Allows working with the fw config itself.")
+				.subcommand_required(true)
+				.subcommand(
+					Command::new("migrate")
+						.about("Upgrades the config to the current schema version (including the legacy ~/.fw.json) after backing it up")
+						.arg(
+							Arg::new("dry-run")
+								.long("dry-run")
+								.help("Only print the migrations that would run")
+								.action(ArgAction::SetTrue),
+						),
+				)
+				.subcommand(
+					Command::new("sync")
+						.about("Commits the changed projects and tags of the config dir, pulls the changes of others from its git remote origin and pushes"),
+				),
+		)
 }
@@ -0,0 +1,286 @@
+use super::path::{FwPaths, fw_path};
+use super::project::{Project, Remote};
+use super::settings::{PersistedSettings, Tag};
+use super::{write_project, write_settings, write_tag};
+use crate::errors::AppError;
+
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, read_to_string};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use walkdir::WalkDir;
+
+/// 1: the legacy ~/.fw.json, 2: settings.toml with projects/ and tags/ directories, 3: the same with a version in settings.toml
+pub const CURRENT_SCHEMA_VERSION: u32 = 3;
+/// settings.toml without a version
+const UNVERSIONED_SCHEMA_VERSION: u32 = 2;
+
+struct Migration {
+	/// Upgrades from this version to the next one
+	from: u32,
+	description: &'static str,
+	/// Gets the version it migrates to
+	apply: fn(&FwPaths, u32) -> Result<(), AppError>,
+}
+
+const MIGRATIONS: &[Migration] = &[
+	Migration {
+		from: 1,
+		description: "convert the legacy ~/.fw.json into settings.toml and project and tag files",
+		apply: migrate_legacy_json,
+	},
+	Migration {
+		from: 2,
+		description: "add the schema version to settings.toml",
+		apply: add_version,
+	},
+];
+
+#[derive(Deserialize, Debug)]
+struct LegacyConfig {
+	projects: BTreeMap<String, LegacyProject>,
+	settings: LegacySettings,
+}
+
+#[derive(Deserialize, Debug)]
+struct LegacyProject {
+	git: String,
+	after_clone: Option<String>,
+	after_workon: Option<String>,
+	override_path: Option<String>,
+	tags: Option<BTreeSet<String>>,
+	additional_remotes: Option<Vec<Remote>>,
+	bare: Option<bool>,
+	#[serde(default)]
+	trusted: bool,
+}
+
+#[derive(Deserialize, Debug)]
+struct LegacySettings {
+	workspace: String,
+	shell: Option<Vec<String>>,
+	default_after_workon: Option<String>,
+	default_after_clone: Option<String>,
+	default_tags: Option<BTreeSet<String>>,
+	tags: Option<BTreeMap<String, Tag>>,
+	github_token: Option<String>,
+}
+
+pub fn legacy_config_path() -> Option<PathBuf> {
+	dirs::home_dir().map(|home| home.join(".fw.json"))
+}
+
+/// The schema version of the config or None if there is no config at all.
+fn schema_version(paths: &FwPaths) -> Result<Option<u32>, AppError> {
+	if paths.settings.exists() {
+		let settings: toml::Table = toml::from_str(&read_to_string(&paths.settings)?)?;
+		let version = match settings.get("version") {
+			Some(version) => version
+				.as_integer()
+				.and_then(|v| u32::try_from(v).ok())
+				.ok_or_else(|| AppError::UserError(format!("Invalid version {version} in {}", paths.settings.display())))?,
+			None => UNVERSIONED_SCHEMA_VERSION,
+		};
+		Ok(Some(version))
+	} else if legacy_config_path().is_some_and(|p| p.exists()) {
+		Ok(Some(1))
+	} else {
+		Ok(None)
+	}
+}
+
+/// Fails for configs written by a newer fw.
+pub fn check_version(version: Option<u32>) -> Result<(), AppError> {
+	match version {
+		Some(version) if version > CURRENT_SCHEMA_VERSION => Err(AppError::UserError(format!(
+			"The config has schema version {version} but this fw only supports up to {CURRENT_SCHEMA_VERSION}. Please update fw."
+		))),
+		_ => Ok(()),
+	}
+}
+
+fn convert_legacy(legacy: LegacyConfig) -> (PersistedSettings, Vec<Project>, BTreeMap<String, Tag>) {
+	let settings = PersistedSettings {
+		version: None,
+		shared_config_dirs: None,
+		workspace: legacy.settings.workspace,
+		shell: legacy.settings.shell,
+		default_after_workon: legacy.settings.default_after_workon,
+		default_after_clone: legacy.settings.default_after_clone,
+		github_token: legacy.settings.github_token,
+		github_token_command: None,
+		github_api_url: None,
+		github_ssh_host: None,
+		ssh_key_files: None,
+		gitlab_url: None,
+		gitlab_token: None,
+		gitea_url: None,
+		gitea_token: None,
+		bitbucket_url: None,
+		bitbucket_token: None,
+		import_tags: None,
+		retries: None,
+		retry_backoff_ms: None,
+	};
+	let projects = legacy
+		.projects
+		.into_iter()
+		.map(|(name, p)| Project {
+			name,
+			git: p.git,
+			after_clone: p.after_clone,
+			after_workon: p.after_workon,
+			override_path: p.override_path,
+			tags: p.tags,
+			additional_remotes: p.additional_remotes,
+			bare: p.bare,
+			trusted: p.trusted,
+			retries: None,
+			retry_backoff_ms: None,
+			branch: None,
+			clone_depth: None,
+			clone_filter: None,
+			sparse_checkout: None,
+			submodules: None,
+			worktrees: None,
+			update_strategy: None,
+			remote_id: None,
+			project_config_path: "default".to_string(),
+		})
+		.collect();
+	// default tags are a property of the tag now
+	let default_tags = legacy.settings.default_tags.unwrap_or_default();
+	let mut tags = legacy.settings.tags.unwrap_or_default();
+	for name in &default_tags {
+		tags
+			.entry(name.clone())
+			.or_insert_with(|| Tag {
+				after_clone: None,
+				after_workon: None,
+				priority: None,
+				workspace: None,
+				default: None,
+				branch: None,
+				clone_depth: None,
+				clone_filter: None,
+				sparse_checkout: None,
+				tag_config_path: String::new(),
+			})
+			.default = Some(true);
+	}
+	for tag in tags.values_mut() {
+		tag.tag_config_path = "default".to_string();
+	}
+	(settings, projects, tags)
+}
+
+fn migrate_legacy_json(_: &FwPaths, _: u32) -> Result<(), AppError> {
+	let legacy_path = legacy_config_path().ok_or(AppError::InternalError("Cannot resolve home dir"))?;
+	let legacy: LegacyConfig = serde_json::from_str(&read_to_string(&legacy_path)?)?;
+	let (settings, projects, tags) = convert_legacy(legacy);
+	write_settings(&settings)?;
+	for project in projects {
+		write_project(&project)?;
+	}
+	for (name, tag) in tags {
+		write_tag(&name, &tag)?;
+	}
+	Ok(())
+}
+
+/// Prepends the version so comments and formatting of the settings survive.
+fn with_version(raw_settings: &str, version: u32) -> String {
+	format!("version = {version}\n{raw_settings}")
+}
+
+fn add_version(paths: &FwPaths, version: u32) -> Result<(), AppError> {
+	let raw_settings = read_to_string(&paths.settings)?;
+	fs::write(&paths.settings, with_version(&raw_settings, version))?;
+	Ok(())
+}
+
+/// Copies the whole config directory next to it.
+fn backup(base: &Path) -> Result<PathBuf, AppError> {
+	let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+	let backup = PathBuf::from(format!("{}.backup-{}", base.display(), timestamp));
+	for maybe_entry in WalkDir::new(base) {
+		let entry = maybe_entry?;
+		let target = backup.join(
+			entry
+				.path()
+				.strip_prefix(base)
+				.map_err(|e| AppError::RuntimeError(format!("Failed to strip prefix: {e}")))?,
+		);
+		if entry.file_type().is_dir() {
+			fs::create_dir_all(&target)?;
+		} else {
+			fs::copy(entry.path(), &target)?;
+		}
+	}
+	Ok(backup)
+}
+
+/// Upgrades the config to the current schema version after backing it up.
+pub fn migrate(dry_run: bool) -> Result<(), AppError> {
+	let paths = fw_path()?;
+	let version = schema_version(&paths)?.ok_or_else(|| AppError::UserError(format!("No fw config found in {}", paths.base.display())))?;
+	check_version(Some(version))?;
+	let pending: Vec<&Migration> = MIGRATIONS.iter().filter(|m| m.from >= version).collect();
+	if pending.is_empty() {
+		println!("Config is up to date (schema version {CURRENT_SCHEMA_VERSION})");
+		return Ok(());
+	}
+	for migration in &pending {
+		let prefix = if dry_run { "WOULD MIGRATE" } else { "MIGRATE" };
+		println!("{}: {} -> {}: {}", prefix, migration.from, migration.from + 1, migration.description);
+	}
+	if dry_run {
+		return Ok(());
+	}
+	if paths.base.exists() {
+		println!("Backed up {} to {}", paths.base.display(), backup(&paths.base)?.display());
+	}
+	for migration in pending {
+		(migration.apply)(&paths, migration.from + 1)?;
+	}
+	println!("Config migrated to schema version {CURRENT_SCHEMA_VERSION}");
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_convert_legacy() {
+		let legacy: LegacyConfig = serde_json::from_str(
+			r#"{"projects":{"fw":{"git":"git@github.com:brocode/fw.git","tags":["rust"]}},"settings":{"workspace":"~/workspace","default_tags":["rust"],"tags":{"rust":{"after_clone":"cargo build"}}}}"#,
+		)
+		.unwrap();
+		let (settings, projects, tags) = convert_legacy(legacy);
+
+		assert_eq!(settings.workspace, "~/workspace");
+		assert_eq!(projects[0].name, "fw");
+		assert_eq!(projects[0].project_config_path, "default");
+		assert_eq!(tags["rust"].default, Some(true));
+		assert_eq!(tags["rust"].after_clone, Some("cargo build".to_owned()));
+	}
+	#[test]
+	fn test_add_version_keeps_settings() {
+		let paths = super::super::path::paths_in(std::env::temp_dir().join(format!("fw-test-migrate-{}", std::process::id())));
+		fs::create_dir_all(&paths.base).unwrap();
+		fs::write(&paths.settings, "workspace = \"~/workspace\"\n").unwrap();
+		let step = MIGRATIONS.iter().find(|m| m.from == UNVERSIONED_SCHEMA_VERSION).unwrap();
+		(step.apply)(&paths, step.from + 1).unwrap();
+		let parsed: PersistedSettings = toml::from_str(&read_to_string(&paths.settings).unwrap()).unwrap();
+		fs::remove_dir_all(&paths.base).unwrap();
+		assert_eq!(parsed.version, Some(3));
+		assert_eq!(parsed.workspace, "~/workspace");
+	}
+	#[test]
+	fn test_newer_version_is_rejected() {
+		assert!(check_version(Some(CURRENT_SCHEMA_VERSION)).is_ok());
+		assert!(check_version(Some(CURRENT_SCHEMA_VERSION + 1)).is_err());
+	}
+}
@@ -4,15 +4,23 @@ use std::collections::{BTreeMap, BTreeSet};
 use std::fs::{self, File, read_to_string};
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
 use walkdir::WalkDir;
 
 static CONF_MODE_HEADER: &str = "# -*- mode: Conf; -*-\n";
+/// The only settings taken from shared config dirs. Everything else either runs commands (`shell`, `*_command`,
+/// `default_after_*`), decides where tokens are sent or is personal.
+const SHARED_SETTINGS: &[&str] = &["workspace", "github_ssh_host", "import_tags", "retries", "retry_backoff_ms"];
 
 pub mod metadata_from_repository;
+pub mod migrate;
 mod path;
 pub mod project;
 pub mod settings;
-use path::{expand_path, fw_path};
+pub mod sync;
+pub use path::expand_path;
+use path::{fw_path, paths_in};
 
 use project::Project;
 use settings::{PersistedSettings, Settings, Tag};
@@ -21,76 +29,291 @@ use settings::{PersistedSettings, Settings, Tag};
 pub struct Config {
 	pub projects: BTreeMap<String, Project>,
 	pub settings: Settings,
+	#[serde(skip)]
+	pub sources: ConfigSources,
 }
 
-pub fn read_config() -> Result<Config, AppError> {
-	let paths = fw_path()?;
+/// The files each project and tag was read from, in the order they were merged.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigSources {
+	pub projects: BTreeMap<String, Vec<PathBuf>>,
+	pub tags: BTreeMap<String, Vec<PathBuf>>,
+}
 
-	let settings_raw = read_to_string(&paths.settings)
-		.map_err(|e| AppError::RuntimeError(format!("Could not read settings file ({}): {}", paths.settings.to_string_lossy(), e)))?;
+impl ConfigSources {
+	/// The files of the project joined with ` < ` because later ones override earlier ones.
+	pub fn describe_project(&self, name: &str) -> String {
+		describe(self.projects.get(name))
+	}
 
-	let settings: PersistedSettings = toml::from_str(&settings_raw)?;
+	pub fn describe_tag(&self, name: &str) -> String {
+		describe(self.tags.get(name))
+	}
+}
 
-	let mut projects: BTreeMap<String, Project> = BTreeMap::new();
-	if paths.projects.exists() {
-		for maybe_project_file in WalkDir::new(&paths.projects).follow_links(true) {
-			let project_file = maybe_project_file?;
-			if project_file.metadata()?.is_file() && !project_file.file_name().to_os_string().eq(".DS_Store") {
-				let raw_project = read_to_string(project_file.path())?;
-				let mut project: Project = match toml::from_str(&raw_project) {
+fn describe(files: Option<&Vec<PathBuf>>) -> String {
+	files
+		.map(|files| files.iter().map(|f| f.to_string_lossy().to_string()).collect::<Vec<String>>().join(" < "))
+		.unwrap_or_else(|| "None".to_owned())
+}
+
+/// A project or tag file before it is merged with the definitions of the same name from other config roots.
+struct Definition {
+	table: toml::Table,
+	config_path: String,
+	file: PathBuf,
+}
+
+/// Reads the project or tag files below `dir`, keyed by file name.
+fn read_definitions(dir: &Path, kind: &str) -> Result<BTreeMap<String, Definition>, AppError> {
+	let mut definitions: BTreeMap<String, Definition> = BTreeMap::new();
+	if dir.exists() {
+		for maybe_file in WalkDir::new(dir).follow_links(true) {
+			let file = maybe_file?;
+			if file.metadata()?.is_file() && !file.file_name().to_os_string().eq(".DS_Store") {
+				let raw = read_to_string(file.path())?;
+				let table: toml::Table = match toml::from_str(&raw) {
 					o @ Ok(_) => o,
 					e @ Err(_) => {
-						eprintln!("There is an issue in your config for project {}", project_file.file_name().to_string_lossy());
+						eprintln!("There is an issue in your config for {} {}", kind, file.path().to_string_lossy());
 						e
 					}
 				}?;
-
-				project.name = project_file
+				let name = file
 					.file_name()
 					.to_str()
 					.map(ToOwned::to_owned)
-					.ok_or(AppError::InternalError("Failed to get project name"))?;
-				project.project_config_path = PathBuf::from(project_file.path().parent().ok_or(AppError::InternalError("Expected file to have a parent"))?)
-					.strip_prefix(paths.projects.as_path())
+					.ok_or(AppError::InternalError("Failed to get file name"))?;
+				let config_path = PathBuf::from(file.path().parent().ok_or(AppError::InternalError("Expected file to have a parent"))?)
+					.strip_prefix(dir)
 					.map_err(|e| AppError::RuntimeError(format!("Failed to strip prefix: {e}")))?
 					.to_string_lossy()
 					.to_string();
-				if projects.contains_key(&project.name) {
-					eprintln!(
-						"Inconsistency found: project {} defined more than once. Will use the project that is found last. Results might be inconsistent.",
-						project.name
-					);
+				if definitions.contains_key(&name) {
+					eprintln!("Inconsistency found: {kind} {name} defined more than once. Will use the {kind} that is found last. Results might be inconsistent.");
 				}
-				projects.insert(project.name.clone(), project);
+				definitions.insert(
+					name,
+					Definition {
+						table,
+						config_path,
+						file: file.path().to_path_buf(),
+					},
+				);
 			}
 		}
 	}
+	Ok(definitions)
+}
 
-	let mut tags: BTreeMap<String, Tag> = BTreeMap::new();
-	if paths.tags.exists() {
-		for maybe_tag_file in WalkDir::new(&paths.tags).follow_links(true) {
-			let tag_file = maybe_tag_file?;
-
-			if tag_file.metadata()?.is_file() && !tag_file.file_name().to_os_string().eq(".DS_Store") {
-				let raw_tag = read_to_string(tag_file.path())?;
-				let mut tag: Tag = toml::from_str(&raw_tag)?;
-				let tag_name: String = tag_file
-					.file_name()
-					.to_str()
-					.map(ToOwned::to_owned)
-					.ok_or(AppError::InternalError("Failed to get tag name"))?;
-				tag.tag_config_path = PathBuf::from(tag_file.path().parent().ok_or(AppError::InternalError("Expected file to have a parent"))?)
-					.strip_prefix(paths.tags.as_path())
-					.map_err(|e| AppError::RuntimeError(format!("Failed to strip prefix: {e}")))?
-					.to_string_lossy()
-					.to_string();
-				if tags.contains_key(&tag_name) {
-					eprintln!("Inconsistency found: tag {tag_name} defined more than once. Will use the project that is found last. Results might be inconsistent.");
+/// A project or tag merged from all config roots.
+struct Merged<T> {
+	value: T,
+	config_path: String,
+	files: Vec<PathBuf>,
+}
+
+/// Fields set in `overlay` replace the ones in `base`, fields it doesn't set are kept.
+fn merge_table(base: &mut toml::Table, overlay: toml::Table) {
+	for (key, value) in overlay {
+		base.insert(key, value);
+	}
+}
+
+/// The fields of `table` that differ from the merged `shared` definition, so that a personal file doesn't pin the
+/// shared fields it didn't change. Fields of the shared definition can't be removed.
+fn without_shared_fields(table: toml::Table, shared: &toml::Table, kind: &str, name: &str) -> Result<toml::Table, AppError> {
+	if let Some(key) = shared.keys().find(|key| !table.contains_key(*key)) {
+		return Err(AppError::UserError(format!(
+			"{key} of {kind} {name} is set in a shared config dir and can't be removed from your own config"
+		)));
+	}
+	Ok(table.into_iter().filter(|(key, value)| shared.get(key) != Some(value)).collect())
+}
+
+/// The projects and tags of the shared config dirs, each merged from all of them.
+#[derive(Default)]
+struct SharedDefinitions {
+	projects: BTreeMap<String, SharedDefinition>,
+	tags: BTreeMap<String, SharedDefinition>,
+}
+
+#[derive(Default)]
+struct SharedDefinition {
+	table: toml::Table,
+	/// The shared config dirs defining it
+	roots: Vec<PathBuf>,
+}
+
+impl SharedDefinitions {
+	fn project(&self, name: &str) -> toml::Table {
+		self.projects.get(name).map(|shared| shared.table.clone()).unwrap_or_default()
+	}
+
+	fn tag(&self, name: &str) -> toml::Table {
+		self.tags.get(name).map(|shared| shared.table.clone()).unwrap_or_default()
+	}
+}
+
+/// Read once per process because fw only ever writes to its own config dir, never to the shared ones.
+static SHARED_DEFINITIONS: OnceLock<SharedDefinitions> = OnceLock::new();
+
+fn shared_definitions() -> Result<&'static SharedDefinitions, AppError> {
+	if let Some(shared) = SHARED_DEFINITIONS.get() {
+		return Ok(shared);
+	}
+	let shared = read_shared_definitions()?;
+	Ok(SHARED_DEFINITIONS.get_or_init(|| shared))
+}
+
+fn read_shared_definitions() -> Result<SharedDefinitions, AppError> {
+	let paths = fw_path()?;
+	if !paths.settings.exists() {
+		return Ok(SharedDefinitions::default());
+	}
+	let own_settings = read_settings_table(&paths)?;
+	let roots = config_roots(paths, &own_settings)?;
+	let shared_roots = &roots[..roots.len() - 1];
+	Ok(SharedDefinitions {
+		projects: merge_shared(shared_roots, |p| &p.projects, "project")?,
+		tags: merge_shared(shared_roots, |p| &p.tags, "tag")?,
+	})
+}
+
+fn merge_shared(roots: &[path::FwPaths], dir: fn(&path::FwPaths) -> &Path, kind: &str) -> Result<BTreeMap<String, SharedDefinition>, AppError> {
+	let mut merged: BTreeMap<String, SharedDefinition> = BTreeMap::new();
+	for root in roots {
+		for (name, definition) in read_definitions(dir(root), kind)? {
+			let shared = merged.entry(name).or_default();
+			merge_table(&mut shared.table, definition.table);
+			shared.roots.push(root.base.clone());
+		}
+	}
+	Ok(merged)
+}
+
+/// Merges the definitions of all roots. A later root overrides the fields it sets and decides the config path.
+fn merge_definitions<T: serde::de::DeserializeOwned>(layers: Vec<BTreeMap<String, Definition>>, kind: &str) -> Result<BTreeMap<String, Merged<T>>, AppError> {
+	let mut merged: BTreeMap<String, Merged<toml::Table>> = BTreeMap::new();
+	for layer in layers {
+		for (name, definition) in layer {
+			let entry = merged.entry(name).or_insert_with(|| Merged {
+				value: toml::Table::new(),
+				config_path: String::new(),
+				files: vec![],
+			});
+			merge_table(&mut entry.value, definition.table);
+			entry.config_path = definition.config_path;
+			entry.files.push(definition.file);
+		}
+	}
+	merged
+		.into_iter()
+		.map(|(name, merged)| {
+			let value: T = merged.value.try_into().map_err(|e| {
+				eprintln!("There is an issue in your config for {kind} {name}");
+				AppError::from(e)
+			})?;
+			Ok((
+				name,
+				Merged {
+					value,
+					config_path: merged.config_path,
+					files: merged.files,
+				},
+			))
+		})
+		.collect()
+}
+
+fn read_settings_table(paths: &path::FwPaths) -> Result<toml::Table, AppError> {
+	let settings_raw = read_to_string(&paths.settings)
+		.map_err(|e| AppError::RuntimeError(format!("Could not read settings file ({}): {}", paths.settings.to_string_lossy(), e)))?;
+	let table: toml::Table = toml::from_str(&settings_raw)?;
+	migrate::check_version(table.get("version").and_then(|v| v.as_integer()).and_then(|v| u32::try_from(v).ok()))?;
+	Ok(table)
+}
+
+/// The config roots in the order they are merged: the `shared_config_dirs` of the settings, then the fw config dir itself.
+fn config_roots(paths: path::FwPaths, settings: &toml::Table) -> Result<Vec<path::FwPaths>, AppError> {
+	let shared: Vec<String> = match settings.get("shared_config_dirs") {
+		Some(dirs) => dirs.clone().try_into()?,
+		None => vec![],
+	};
+	let mut roots: Vec<path::FwPaths> = shared.into_iter().map(|dir| paths_in(expand_path(PathBuf::from(dir)))).collect();
+	for root in &roots {
+		if !root.base.exists() {
+			return Err(AppError::UserError(format!("Shared config dir {} does not exist", root.base.display())));
+		}
+	}
+	roots.push(paths);
+	Ok(roots)
+}
+
+pub fn read_config() -> Result<Config, AppError> {
+	let paths = fw_path()?;
+
+	if !paths.settings.exists() && migrate::legacy_config_path().is_some_and(|p| p.exists()) {
+		return Err(AppError::UserError(
+			"Found a legacy ~/.fw.json but no settings.toml. Run `fw config migrate` to convert it.".to_owned(),
+		));
+	}
+	let own_settings = read_settings_table(&paths)?;
+	let roots = config_roots(paths, &own_settings)?;
+
+	let mut settings_table = toml::Table::new();
+	for root in &roots[..roots.len() - 1] {
+		if root.settings.exists() {
+			let mut shared_settings = read_settings_table(root)?;
+			shared_settings.retain(|key, _| {
+				let shared = SHARED_SETTINGS.contains(&key);
+				if !shared && key != "version" {
+					eprintln!(
+						"Ignoring {} in {}: only {} can be shared",
+						key,
+						root.settings.display(),
+						SHARED_SETTINGS.join(", ")
+					);
 				}
-				tags.insert(tag_name, tag);
-			}
+				shared
+			});
+			merge_table(&mut settings_table, shared_settings);
 		}
 	}
+	merge_table(&mut settings_table, own_settings);
+	let settings: PersistedSettings = settings_table.try_into()?;
+
+	let mut sources = ConfigSources::default();
+
+	let project_layers = roots
+		.iter()
+		.map(|root| read_definitions(&root.projects, "project"))
+		.collect::<Result<Vec<_>, _>>()?;
+	let mut projects: BTreeMap<String, Project> = BTreeMap::new();
+	for (name, merged) in merge_definitions::<Project>(project_layers, "project")? {
+		sources.projects.insert(name.clone(), merged.files);
+		projects.insert(
+			name.clone(),
+			Project {
+				name,
+				project_config_path: merged.config_path,
+				..merged.value
+			},
+		);
+	}
+
+	let tag_layers = roots.iter().map(|root| read_definitions(&root.tags, "tag")).collect::<Result<Vec<_>, _>>()?;
+	let mut tags: BTreeMap<String, Tag> = BTreeMap::new();
+	for (name, merged) in merge_definitions::<Tag>(tag_layers, "tag")? {
+		sources.tags.insert(name.clone(), merged.files);
+		tags.insert(
+			name,
+			Tag {
+				tag_config_path: merged.config_path,
+				..merged.value
+			},
+		);
+	}
 
 	let default_tags: BTreeSet<String> = tags
 		.iter()
@@ -108,10 +331,70 @@ pub fn read_config() -> Result<Config, AppError> {
 			default_after_clone: settings.default_after_clone,
 			default_tags: Some(default_tags),
 			github_token: settings.github_token,
+			github_token_command: settings.github_token_command,
+			github_api_url: settings.github_api_url,
+			github_ssh_host: settings.github_ssh_host,
+			ssh_key_files: settings.ssh_key_files,
+			gitlab_url: settings.gitlab_url,
+			gitlab_token: settings.gitlab_token,
+			gitea_url: settings.gitea_url,
+			gitea_token: settings.gitea_token,
+			bitbucket_url: settings.bitbucket_url,
+			bitbucket_token: settings.bitbucket_token,
+			import_tags: settings.import_tags,
+			retries: settings.retries,
+			retry_backoff_ms: settings.retry_backoff_ms,
 		},
+		sources,
 	})
 }
 
+/// Projects and tags that are defined in more than one subdirectory of the config roots. Only one of them is used.
+pub struct Duplicates {
+	/// Name -> directories
+	pub projects: BTreeMap<String, Vec<String>>,
+	pub tags: BTreeMap<String, Vec<String>>,
+}
+
+pub fn find_duplicates() -> Result<Duplicates, AppError> {
+	let paths = fw_path()?;
+	let own_settings = read_settings_table(&paths)?;
+	let roots = config_roots(paths, &own_settings)?;
+	Ok(Duplicates {
+		projects: duplicate_definitions(roots.iter().map(|root| root.projects.as_path()))?,
+		tags: duplicate_definitions(roots.iter().map(|root| root.tags.as_path()))?,
+	})
+}
+
+/// Definitions of the same name in different subdirectories, of one root or across roots. The same subdirectory in a
+/// later root overrides fields of the earlier one and is no duplicate.
+fn duplicate_definitions<'a>(dirs: impl Iterator<Item = &'a Path>) -> Result<BTreeMap<String, Vec<String>>, AppError> {
+	// name -> config path -> directories
+	let mut definitions: BTreeMap<String, BTreeMap<String, Vec<String>>> = BTreeMap::new();
+	for dir in dirs.filter(|dir| dir.exists()) {
+		for maybe_file in WalkDir::new(dir).follow_links(true) {
+			let file = maybe_file?;
+			if file.metadata()?.is_file() && !file.file_name().to_os_string().eq(".DS_Store") {
+				let parent = file.path().parent().unwrap_or(dir);
+				let config_path = parent.strip_prefix(dir).map(|p| p.to_string_lossy().to_string()).unwrap_or_default();
+				definitions
+					.entry(file.file_name().to_string_lossy().to_string())
+					.or_default()
+					.entry(config_path)
+					.or_default()
+					.push(parent.to_string_lossy().to_string());
+			}
+		}
+	}
+	Ok(
+		definitions
+			.into_iter()
+			.filter(|(_, by_config_path)| by_config_path.len() > 1)
+			.map(|(name, by_config_path)| (name, by_config_path.into_values().flatten().collect()))
+			.collect(),
+	)
+}
+
 pub fn write_settings(settings: &PersistedSettings) -> Result<(), AppError> {
 	let paths = fw_path()?;
 	paths.ensure_base_exists()?;
@@ -136,9 +419,10 @@ pub fn write_tag(tag_name: &str, tag: &Tag) -> Result<(), AppError> {
 	let mut tag_file_path = tag_path;
 	tag_file_path.push(tag_name);
 
+	let table = without_shared_fields(toml::Table::try_from(tag)?, &shared_definitions()?.tag(tag_name), "tag", tag_name)?;
 	let mut buffer = File::create(&tag_file_path)
 		.map_err(|e| AppError::RuntimeError(format!("Failed to create project config file '{}'. {}", tag_file_path.to_string_lossy(), e)))?;
-	let serialized = toml::to_string_pretty(&tag)?;
+	let serialized = toml::to_string_pretty(&table)?;
 	write!(buffer, "{CONF_MODE_HEADER}")?;
 	write!(buffer, "{serialized}")?;
 	write_example(&mut buffer, Tag::example())?;
@@ -153,8 +437,7 @@ pub fn delete_tag_config(tag_name: &str, tag: &Tag) -> Result<(), AppError> {
 	tag_file_path.push(PathBuf::from(&tag.tag_config_path));
 	tag_file_path.push(tag_name);
 
-	fs::remove_file(&tag_file_path).map_err(|e| AppError::RuntimeError(format!("Failed to delete tag config from '{tag_file_path:?}': {e}")))?;
-	Ok(())
+	delete_definition(&tag_file_path, shared_definitions()?.tags.get(tag_name), "tag", tag_name)
 }
 
 pub fn delete_project_config(project: &Project) -> Result<(), AppError> {
@@ -165,8 +448,20 @@ pub fn delete_project_config(project: &Project) -> Result<(), AppError> {
 	project_file_path.push(PathBuf::from(&project.project_config_path));
 	project_file_path.push(&project.name);
 
-	fs::remove_file(project_file_path).map_err(|e| AppError::RuntimeError(format!("Failed to delete project config: {e}")))?;
-	Ok(())
+	delete_definition(&project_file_path, shared_definitions()?.projects.get(&project.name), "project", &project.name)
+}
+
+/// Deletes the own file of a project or tag. Refused for definitions from a shared config dir because they would stay
+/// in the config, with or without the own file.
+fn delete_definition(file: &Path, shared: Option<&SharedDefinition>, kind: &str, name: &str) -> Result<(), AppError> {
+	if let Some(shared) = shared {
+		let roots: Vec<String> = shared.roots.iter().map(|root| root.to_string_lossy().to_string()).collect();
+		return Err(AppError::UserError(format!(
+			"{kind} {name} is defined in the shared config dir {} and can only be removed there",
+			roots.join(", ")
+		)));
+	}
+	fs::remove_file(file).map_err(|e| AppError::RuntimeError(format!("Failed to delete {kind} config from '{}': {e}", file.to_string_lossy())))
 }
 
 fn write_example<T>(buffer: &mut File, example: T) -> Result<(), AppError>
@@ -195,9 +490,15 @@ pub fn write_project(project: &Project) -> Result<(), AppError> {
 	let mut project_file_path = project_path;
 	project_file_path.push(&project.name);
 
+	let table = without_shared_fields(
+		toml::Table::try_from(project)?,
+		&shared_definitions()?.project(&project.name),
+		"project",
+		&project.name,
+	)?;
 	let mut buffer: File = File::create(&project_file_path)
 		.map_err(|e| AppError::RuntimeError(format!("Failed to create project config file '{}'. {}", project_file_path.to_string_lossy(), e)))?;
-	let serialized = toml::to_string_pretty(&project)?;
+	let serialized = toml::to_string_pretty(&table)?;
 
 	write!(buffer, "{CONF_MODE_HEADER}")?;
 	write!(buffer, "{serialized}")?;
@@ -215,6 +516,15 @@ impl Config {
 		expand_path(path)
 	}
 
+	/// All workspace directories: the one from the settings and the ones set by tags.
+	pub fn workspaces(&self) -> BTreeSet<PathBuf> {
+		let tag_workspaces = self.settings.tags.clone().unwrap_or_default().into_values().flat_map(|t| t.workspace);
+		std::iter::once(self.settings.workspace.clone())
+			.chain(tag_workspaces)
+			.map(|w| expand_path(PathBuf::from(w)))
+			.collect()
+	}
+
 	fn resolve_workspace(&self, project: &Project) -> String {
 		let mut x = self.resolve_from_tags(|tag| tag.workspace.clone(), project.tags.clone());
 
@@ -235,6 +545,37 @@ impl Config {
 		commands
 	}
 
+	/// The branch from the project or, if not set, from the tag with the highest priority.
+	pub fn resolve_branch(&self, project: &Project) -> Option<String> {
+		project
+			.branch
+			.clone()
+			.or_else(|| self.resolve_from_tags(|tag| tag.branch.clone(), project.tags.clone()).pop())
+	}
+	pub fn resolve_clone_depth(&self, project: &Project) -> Option<u32> {
+		project
+			.clone_depth
+			.or_else(|| self.resolve_from_tags(|tag| tag.clone_depth, project.tags.clone()).pop())
+	}
+	pub fn resolve_clone_filter(&self, project: &Project) -> Option<String> {
+		project
+			.clone_filter
+			.clone()
+			.or_else(|| self.resolve_from_tags(|tag| tag.clone_filter.clone(), project.tags.clone()).pop())
+	}
+	pub fn resolve_sparse_checkout(&self, project: &Project) -> Option<Vec<String>> {
+		project
+			.sparse_checkout
+			.clone()
+			.or_else(|| self.resolve_from_tags(|tag| tag.sparse_checkout.clone(), project.tags.clone()).pop())
+	}
+	pub fn resolve_retries(&self, project: &Project) -> u32 {
+		project.retries.or(self.settings.retries).unwrap_or(0)
+	}
+	pub fn resolve_retry_backoff(&self, project: &Project) -> Duration {
+		Duration::from_millis(project.retry_backoff_ms.or(self.settings.retry_backoff_ms).unwrap_or(1000))
+	}
+
 	fn resolve_workon_from_tags(&self, maybe_tags: Option<BTreeSet<String>>) -> Vec<String> {
 		self.resolve_from_tags(|t| t.clone().after_workon, maybe_tags)
 	}
@@ -246,12 +587,12 @@ impl Config {
 		tag.priority.unwrap_or(50)
 	}
 
-	fn resolve_from_tags<F>(&self, resolver: F, maybe_tags: Option<BTreeSet<String>>) -> Vec<String>
+	fn resolve_from_tags<F, T>(&self, resolver: F, maybe_tags: Option<BTreeSet<String>>) -> Vec<T>
 	where
-		F: Fn(&Tag) -> Option<String>,
+		F: Fn(&Tag) -> Option<T>,
 	{
 		if let (Some(tags), Some(settings_tags)) = (maybe_tags, self.clone().settings.tags) {
-			let mut resolved_with_priority: Vec<(String, u8)> = tags
+			let mut resolved_with_priority: Vec<(T, u8)> = tags
 				.iter()
 				.flat_map(|t| match settings_tags.get(t) {
 					None => {
@@ -274,6 +615,89 @@ mod tests {
 	use super::*;
 	use maplit::btreeset;
 
+	fn definition(raw: &str, config_path: &str, file: &str) -> BTreeMap<String, Definition> {
+		BTreeMap::from([(
+			"fw".to_owned(),
+			Definition {
+				table: toml::from_str(raw).unwrap(),
+				config_path: config_path.to_owned(),
+				file: PathBuf::from(file),
+			},
+		)])
+	}
+
+	#[test]
+	fn test_later_roots_override_fields_they_set() {
+		let shared = definition("git = \"git@github.com:brocode/fw.git\"\ntags = [\"rust\"]", "team", "/shared/projects/team/fw");
+		let personal = definition("override_path = \"/home/me/fw\"\ntags = [\"mine\"]", "default", "/home/me/projects/default/fw");
+		let merged = merge_definitions::<Project>(vec![shared, personal], "project").unwrap().remove("fw").unwrap();
+
+		assert_eq!(merged.value.git, "git@github.com:brocode/fw.git");
+		assert_eq!(merged.value.override_path, Some("/home/me/fw".to_owned()));
+		assert_eq!(merged.value.tags, Some(btreeset!["mine".to_owned()]));
+		assert_eq!(merged.config_path, "default");
+		assert_eq!(
+			merged.files,
+			vec![PathBuf::from("/shared/projects/team/fw"), PathBuf::from("/home/me/projects/default/fw")]
+		);
+	}
+
+	#[test]
+	fn test_only_fields_differing_from_shared_are_written() {
+		let shared = definition("git = \"git@github.com:brocode/fw.git\"\ntags = [\"rust\"]", "team", "/shared/projects/team/fw")
+			.remove("fw")
+			.unwrap()
+			.table;
+		let edited: toml::Table = toml::from_str("git = \"git@github.com:brocode/fw.git\"\ntags = [\"rust\", \"cli\"]").unwrap();
+		let written = without_shared_fields(edited, &shared, "project", "fw").unwrap();
+		assert_eq!(written.keys().collect::<Vec<_>>(), vec!["tags"]);
+
+		let removed: toml::Table = toml::from_str("tags = [\"rust\"]").unwrap();
+		assert!(without_shared_fields(removed, &shared, "project", "fw").is_err());
+	}
+	#[test]
+	fn test_shared_definitions_are_not_deleted() {
+		let dir = std::env::temp_dir().join(format!("fw-test-delete-{}", std::process::id()));
+		fs::create_dir_all(&dir).unwrap();
+		let shared = SharedDefinition {
+			table: toml::from_str("git = \"git@github.com:brocode/fw.git\"").unwrap(),
+			roots: vec![PathBuf::from("/team/fw-config")],
+		};
+
+		// only defined in the shared config dir
+		let error = delete_definition(&dir.join("fw"), Some(&shared), "project", "fw").unwrap_err();
+		assert!(error.to_string().contains("/team/fw-config"));
+
+		// with an own file overriding some fields
+		fs::write(dir.join("fw"), "tags = [\"mine\"]").unwrap();
+		let error = delete_definition(&dir.join("fw"), Some(&shared), "project", "fw").unwrap_err();
+		assert!(error.to_string().contains("/team/fw-config"));
+		assert!(dir.join("fw").exists());
+
+		delete_definition(&dir.join("fw"), None, "project", "fw").unwrap();
+		assert!(!dir.join("fw").exists());
+		fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn test_duplicates_across_roots() {
+		let base = std::env::temp_dir().join(format!("fw-test-duplicates-{}", std::process::id()));
+		let (shared, own) = (base.join("shared"), base.join("own"));
+		for dir in [shared.join("team"), own.join("team"), own.join("default")] {
+			fs::create_dir_all(&dir).unwrap();
+		}
+		// overrides the shared definition
+		fs::write(shared.join("team/fw"), "").unwrap();
+		fs::write(own.join("team/fw"), "").unwrap();
+		// defined next to the shared one
+		fs::write(shared.join("team/infra"), "").unwrap();
+		fs::write(own.join("default/infra"), "").unwrap();
+
+		let duplicates = duplicate_definitions([shared.as_path(), own.as_path()].into_iter()).unwrap();
+		fs::remove_dir_all(&base).unwrap();
+		assert_eq!(duplicates.keys().collect::<Vec<_>>(), vec!["infra"]);
+		assert_eq!(duplicates["infra"].len(), 2);
+	}
 	#[test]
 	fn test_workon_from_tags() {
 		let config = a_config();
@@ -335,6 +759,66 @@ mod tests {
 		assert_eq!(resolved, vec!["clone1".to_string(), "clone override in project".to_owned()]);
 	}
 
+	#[test]
+	fn test_branch_from_tags() {
+		let mut config = a_config();
+		if let Some(tags) = config.settings.tags.as_mut() {
+			tags.get_mut("tag3").unwrap().branch = Some("release".to_owned());
+			tags.get_mut("tag4").unwrap().branch = Some("develop".to_owned());
+		}
+		assert_eq!(config.resolve_branch(config.projects.get("test5").unwrap()), Some("release".to_owned()));
+	}
+	#[test]
+	fn test_branch_from_project_overrides_tags() {
+		let mut config = a_config();
+		if let Some(tags) = config.settings.tags.as_mut() {
+			tags.get_mut("tag3").unwrap().branch = Some("release".to_owned());
+		}
+		let project = Project {
+			branch: Some("main".to_owned()),
+			..config.projects.get("test5").unwrap().clone()
+		};
+		assert_eq!(config.resolve_branch(&project), Some("main".to_owned()));
+	}
+	#[test]
+	fn test_clone_depth_from_tags() {
+		let mut config = a_config();
+		if let Some(tags) = config.settings.tags.as_mut() {
+			tags.get_mut("tag1").unwrap().clone_depth = Some(1);
+		}
+		assert_eq!(config.resolve_clone_depth(config.projects.get("test3").unwrap()), Some(1));
+		assert_eq!(config.resolve_clone_depth(config.projects.get("test4").unwrap()), None);
+	}
+	#[test]
+	fn test_retries_default() {
+		let config = a_config();
+		let project = config.projects.get("test1").unwrap();
+		assert_eq!(config.resolve_retries(project), 0);
+		assert_eq!(config.resolve_retry_backoff(project), Duration::from_millis(1000));
+	}
+	#[test]
+	fn test_retries_from_project_override_settings() {
+		let mut config = a_config();
+		config.settings.retries = Some(1);
+		config.settings.retry_backoff_ms = Some(200);
+		let project = Project {
+			retries: Some(5),
+			..config.projects.get("test1").unwrap().clone()
+		};
+		assert_eq!(config.resolve_retries(&project), 5);
+		assert_eq!(config.resolve_retry_backoff(&project), Duration::from_millis(200));
+	}
+
+	#[test]
+	fn test_workspaces_include_tag_workspaces() {
+		let mut config = a_config();
+		if let Some(tags) = config.settings.tags.as_mut() {
+			tags.get_mut("tag1").unwrap().workspace = Some("/other".to_owned());
+			tags.get_mut("tag2").unwrap().workspace = Some("/test".to_owned());
+		}
+		assert_eq!(config.workspaces(), btreeset![PathBuf::from("/other"), PathBuf::from("/test")]);
+	}
+
 	fn a_config() -> Config {
 		let project = Project {
 			name: "test1".to_owned(),
@@ -346,6 +830,16 @@ mod tests {
 			additional_remotes: None,
 			bare: None,
 			trusted: false,
+			retries: None,
+			retry_backoff_ms: None,
+			branch: None,
+			clone_depth: None,
+			clone_filter: None,
+			sparse_checkout: None,
+			submodules: None,
+			worktrees: None,
+			update_strategy: None,
+			remote_id: None,
 			project_config_path: "".to_string(),
 		};
 		let project2 = Project {
@@ -358,6 +852,16 @@ mod tests {
 			additional_remotes: None,
 			bare: None,
 			trusted: false,
+			retries: None,
+			retry_backoff_ms: None,
+			branch: None,
+			clone_depth: None,
+			clone_filter: None,
+			sparse_checkout: None,
+			submodules: None,
+			worktrees: None,
+			update_strategy: None,
+			remote_id: None,
 			project_config_path: "".to_string(),
 		};
 		let project3 = Project {
@@ -370,6 +874,16 @@ mod tests {
 			additional_remotes: None,
 			bare: None,
 			trusted: false,
+			retries: None,
+			retry_backoff_ms: None,
+			branch: None,
+			clone_depth: None,
+			clone_filter: None,
+			sparse_checkout: None,
+			submodules: None,
+			worktrees: None,
+			update_strategy: None,
+			remote_id: None,
 			project_config_path: "".to_string(),
 		};
 		let project4 = Project {
@@ -382,6 +896,16 @@ mod tests {
 			additional_remotes: None,
 			bare: None,
 			trusted: false,
+			retries: None,
+			retry_backoff_ms: None,
+			branch: None,
+			clone_depth: None,
+			clone_filter: None,
+			sparse_checkout: None,
+			submodules: None,
+			worktrees: None,
+			update_strategy: None,
+			remote_id: None,
 			project_config_path: "".to_string(),
 		};
 		let project5 = Project {
@@ -394,6 +918,16 @@ mod tests {
 			additional_remotes: None,
 			bare: None,
 			trusted: false,
+			retries: None,
+			retry_backoff_ms: None,
+			branch: None,
+			clone_depth: None,
+			clone_filter: None,
+			sparse_checkout: None,
+			submodules: None,
+			worktrees: None,
+			update_strategy: None,
+			remote_id: None,
 			project_config_path: "".to_string(),
 		};
 		let tag1 = Tag {
@@ -402,6 +936,10 @@ mod tests {
 			priority: None,
 			workspace: None,
 			default: None,
+			branch: None,
+			clone_depth: None,
+			clone_filter: None,
+			sparse_checkout: None,
 			tag_config_path: "".to_string(),
 		};
 		let tag2 = Tag {
@@ -410,6 +948,10 @@ mod tests {
 			priority: None,
 			workspace: None,
 			default: None,
+			branch: None,
+			clone_depth: None,
+			clone_filter: None,
+			sparse_checkout: None,
 			tag_config_path: "".to_string(),
 		};
 		let tag3 = Tag {
@@ -418,6 +960,10 @@ mod tests {
 			priority: Some(100),
 			workspace: None,
 			default: None,
+			branch: None,
+			clone_depth: None,
+			clone_filter: None,
+			sparse_checkout: None,
 			tag_config_path: "".to_string(),
 		};
 		let tag4 = Tag {
@@ -426,6 +972,10 @@ mod tests {
 			priority: Some(0),
 			workspace: None,
 			default: None,
+			branch: None,
+			clone_depth: None,
+			clone_filter: None,
+			sparse_checkout: None,
 			tag_config_path: "".to_string(),
 		};
 		let mut projects: BTreeMap<String, Project> = BTreeMap::new();
@@ -447,7 +997,24 @@ mod tests {
 			shell: None,
 			tags: Some(tags),
 			github_token: None,
+			github_token_command: None,
+			github_api_url: None,
+			github_ssh_host: None,
+			ssh_key_files: None,
+			gitlab_url: None,
+			gitlab_token: None,
+			gitea_url: None,
+			gitea_token: None,
+			bitbucket_url: None,
+			bitbucket_token: None,
+			import_tags: None,
+			retries: None,
+			retry_backoff_ms: None,
 		};
-		Config { projects, settings }
+		Config {
+			projects,
+			settings,
+			sources: ConfigSources::default(),
+		}
 	}
 }
@@ -42,6 +42,11 @@ pub fn fw_path() -> Result<FwPaths, AppError> {
 		})
 		.ok_or(AppError::InternalError("Cannot resolve fw config dir"))?;
 
+	Ok(paths_in(base))
+}
+
+/// The paths of a config root, e.g. one of the `shared_config_dirs`.
+pub fn paths_in(base: PathBuf) -> FwPaths {
 	let mut settings = base.clone();
 
 	let env: String = env::var_os("FW_ENV")
@@ -58,12 +63,12 @@ pub fn fw_path() -> Result<FwPaths, AppError> {
 	let mut tags = base.clone();
 	tags.push("tags");
 
-	Ok(FwPaths {
+	FwPaths {
 		settings,
 		base,
 		projects,
 		tags,
-	})
+	}
 }
 
 #[cfg(test)]
@@ -1,6 +1,9 @@
 use maplit::btreeset;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use crate::errors::AppError;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Remote {
@@ -8,6 +11,47 @@ pub struct Remote {
 	pub git: String,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Submodules {
+	Recursive,
+	None,
+}
+
+/// How `fw sync` brings the checked out branch up to date with its upstream.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum UpdateStrategy {
+	#[default]
+	Ff,
+	Rebase,
+	AutostashRebase,
+	FetchOnly,
+}
+
+impl FromStr for UpdateStrategy {
+	type Err = AppError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"ff" => Ok(UpdateStrategy::Ff),
+			"rebase" => Ok(UpdateStrategy::Rebase),
+			"autostash-rebase" => Ok(UpdateStrategy::AutostashRebase),
+			"fetch-only" => Ok(UpdateStrategy::FetchOnly),
+			other => Err(AppError::UserError(format!(
+				"Unknown update strategy {other}. Use ff, rebase, autostash-rebase or fetch-only."
+			))),
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Worktree {
+	pub branch: String,
+	/// Defaults to `<project path>-<branch>`. Relative paths are resolved against the project path.
+	pub path: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Project {
 	#[serde(skip)]
@@ -21,8 +65,19 @@ pub struct Project {
 	pub after_workon: Option<String>,
 	pub override_path: Option<String>,
 	pub bare: Option<bool>,
+	pub branch: Option<String>,
+	pub clone_depth: Option<u32>,
+	pub clone_filter: Option<String>,
+	pub sparse_checkout: Option<Vec<String>>,
+	pub submodules: Option<Submodules>,
+	pub worktrees: Option<Vec<Worktree>>,
+	pub update_strategy: Option<UpdateStrategy>,
+	/// Id of the repository at the hosting provider. Set by org-import to recognize renamed repositories.
+	pub remote_id: Option<String>,
 	pub tags: Option<BTreeSet<String>>,
 	pub additional_remotes: Option<Vec<Remote>>,
+	pub retries: Option<u32>,
+	pub retry_backoff_ms: Option<u64>,
 
 	#[serde(skip)]
 	pub project_config_path: String,
@@ -43,6 +98,19 @@ impl Project {
 			}]),
 			bare: Some(false),
 			trusted: false,
+			retries: Some(2),
+			retry_backoff_ms: Some(1000),
+			branch: Some("main".to_string()),
+			clone_depth: Some(1),
+			clone_filter: Some("blob:none".to_string()),
+			sparse_checkout: Some(vec!["src".to_string(), "doc".to_string()]),
+			submodules: Some(Submodules::Recursive),
+			worktrees: Some(vec![Worktree {
+				branch: "release".to_string(),
+				path: Some("../fw-release".to_string()),
+			}]),
+			update_strategy: Some(UpdateStrategy::Rebase),
+			remote_id: None,
 			project_config_path: "".to_string(), // ignored
 		}
 	}
@@ -1,5 +1,16 @@
+use super::expand_path;
+use crate::errors::AppError;
+
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+/// The github token of this process. Resolved once because the token command may prompt and projects are synced in parallel.
+static GITHUB_TOKEN: OnceLock<Result<Option<String>, String>> = OnceLock::new();
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Tag {
@@ -8,6 +19,10 @@ pub struct Tag {
 	pub priority: Option<u8>,
 	pub workspace: Option<String>,
 	pub default: Option<bool>,
+	pub branch: Option<String>,
+	pub clone_depth: Option<u32>,
+	pub clone_filter: Option<String>,
+	pub sparse_checkout: Option<Vec<String>>,
 
 	#[serde(skip)]
 	pub tag_config_path: String,
@@ -21,12 +36,48 @@ impl Tag {
 			priority: Some(0),
 			workspace: Some("/home/other".to_string()),
 			default: Some(false),
+			branch: Some("release".to_string()),
+			clone_depth: Some(1),
+			clone_filter: None,
+			sparse_checkout: None,
 			tag_config_path: "".to_string(), // ignored
 		}
 	}
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Tags given to projects created by org-import, depending on the metadata of the repository.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ImportTags {
+	/// Topic -> tag
+	pub topics: Option<BTreeMap<String, String>>,
+	/// Primary language (ignoring case) -> tag
+	pub languages: Option<BTreeMap<String, String>>,
+	/// Tag for archived repositories
+	pub archived: Option<String>,
+}
+
+impl ImportTags {
+	pub fn tags_for(&self, topics: &[String], language: Option<&str>, archived: bool) -> BTreeSet<String> {
+		let mut tags = BTreeSet::new();
+		if let Some(ref mapping) = self.topics {
+			tags.extend(topics.iter().filter_map(|topic| mapping.get(topic)).cloned());
+		}
+		if let (Some(mapping), Some(language)) = (&self.languages, language) {
+			tags.extend(
+				mapping
+					.iter()
+					.filter(|(mapped_language, _)| mapped_language.eq_ignore_ascii_case(language))
+					.map(|(_, tag)| tag.clone()),
+			);
+		}
+		if archived {
+			tags.extend(self.archived.clone());
+		}
+		tags
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct Settings {
 	pub workspace: String,
 	pub shell: Option<Vec<String>>,
@@ -34,32 +85,216 @@ pub struct Settings {
 	pub default_after_clone: Option<String>,
 	pub default_tags: Option<BTreeSet<String>>,
 	pub tags: Option<BTreeMap<String, Tag>>,
+	/// The token itself or `file:<path>` to read it from a file
 	pub github_token: Option<String>,
+	/// Command printing the token, run through the shell, e.g. `pass show github`
+	pub github_token_command: Option<String>,
+	/// GraphQL endpoint of GitHub Enterprise, e.g. https://github.example.com/api/graphql
+	pub github_api_url: Option<String>,
+	/// Host used in the ssh clone urls of imported repositories instead of the one GitHub reports, e.g. an alias from ~/.ssh/config
+	pub github_ssh_host: Option<String>,
+	pub ssh_key_files: Option<Vec<String>>,
+	pub gitlab_url: Option<String>,
+	pub gitlab_token: Option<String>,
+	pub gitea_url: Option<String>,
+	pub gitea_token: Option<String>,
+	pub bitbucket_url: Option<String>,
+	pub bitbucket_token: Option<String>,
+	pub import_tags: Option<ImportTags>,
+	pub retries: Option<u32>,
+	pub retry_backoff_ms: Option<u64>,
 }
 
 impl Settings {
 	pub fn get_shell_or_default(self: &Settings) -> Vec<String> {
 		self.shell.clone().unwrap_or_else(|| vec!["sh".to_owned(), "-c".to_owned()])
 	}
+
+	/// Whether a github token is configured. Unlike `resolve_github_token` this doesn't run the command or read the file.
+	pub fn has_github_token(&self) -> bool {
+		env::var_os("FW_GITHUB_TOKEN").is_some() || self.github_token_command.is_some() || self.github_token.is_some()
+	}
+
+	/// The github token from FW_GITHUB_TOKEN, the output of github_token_command or github_token (read from the file
+	/// for `file:<path>`), in this order. Resolved on the first call only, later calls return the same token or error.
+	pub fn resolve_github_token(&self) -> Result<Option<String>, AppError> {
+		GITHUB_TOKEN
+			.get_or_init(|| {
+				self.read_github_token().map_err(|e| match e {
+					AppError::UserError(message) => message,
+					e => e.to_string(),
+				})
+			})
+			.clone()
+			.map_err(AppError::UserError)
+	}
+
+	fn read_github_token(&self) -> Result<Option<String>, AppError> {
+		if let Some(token) = env::var_os("FW_GITHUB_TOKEN") {
+			return Ok(Some(token.to_string_lossy().to_string()));
+		}
+		if let Some(ref command) = self.github_token_command {
+			return token_from_command(&self.get_shell_or_default(), command).map(Some);
+		}
+		match self.github_token {
+			Some(ref token) => match token.strip_prefix("file:") {
+				Some(path) => token_from_file(&expand_path(PathBuf::from(path))).map(Some),
+				None => Ok(Some(token.clone())),
+			},
+			None => Ok(None),
+		}
+	}
+
+	/// The gitlab token from FW_GITLAB_TOKEN or the settings file.
+	pub fn resolve_gitlab_token(&self) -> Option<String> {
+		env::var_os("FW_GITLAB_TOKEN")
+			.map(|s| s.to_string_lossy().to_string())
+			.or_else(|| self.gitlab_token.clone())
+	}
+
+	/// The gitea token from FW_GITEA_TOKEN or the settings file.
+	pub fn resolve_gitea_token(&self) -> Option<String> {
+		env::var_os("FW_GITEA_TOKEN")
+			.map(|s| s.to_string_lossy().to_string())
+			.or_else(|| self.gitea_token.clone())
+	}
+
+	/// The bitbucket token from FW_BITBUCKET_TOKEN or the settings file.
+	pub fn resolve_bitbucket_token(&self) -> Option<String> {
+		env::var_os("FW_BITBUCKET_TOKEN")
+			.map(|s| s.to_string_lossy().to_string())
+			.or_else(|| self.bitbucket_token.clone())
+	}
+
+	/// The GraphQL endpoint of github, api.github.com if not set.
+	pub fn github_api_url_or_default(&self) -> String {
+		self.github_api_url.clone().unwrap_or_else(|| "https://api.github.com/graphql".to_owned())
+	}
+
+	/// The base URL of the gitlab instance, gitlab.com if not set.
+	pub fn gitlab_url_or_default(&self) -> String {
+		self.gitlab_url.clone().unwrap_or_else(|| "https://gitlab.com".to_owned())
+	}
+}
+
+fn non_empty_token(token: &str, source: &str) -> Result<String, AppError> {
+	let token = token.trim();
+	if token.is_empty() {
+		Err(AppError::UserError(format!("The github token from {source} is empty")))
+	} else {
+		Ok(token.to_owned())
+	}
+}
+
+fn token_from_command(shell: &[String], command: &str) -> Result<String, AppError> {
+	let (program, args) = shell.split_first().ok_or_else(|| AppError::UserError("shell is empty".to_owned()))?;
+	let output = Command::new(program)
+		.args(args)
+		.arg(command)
+		.stdin(Stdio::inherit())
+		.stderr(Stdio::inherit())
+		.output()
+		.map_err(|e| AppError::UserError(format!("Could not run github_token_command {command}: {e}")))?;
+	if !output.status.success() {
+		return Err(AppError::UserError(format!("github_token_command {command} failed with {}", output.status)));
+	}
+	non_empty_token(&String::from_utf8_lossy(&output.stdout), "github_token_command")
+}
+
+fn token_from_file(path: &Path) -> Result<String, AppError> {
+	let token = fs::read_to_string(path).map_err(|e| AppError::UserError(format!("Could not read the github token from {}: {e}", path.display())))?;
+	non_empty_token(&token, &path.to_string_lossy())
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct PersistedSettings {
+	/// Schema version of the config, see `fw config migrate`
+	pub version: Option<u32>,
+	/// Config dirs merged before this one, e.g. a checkout of the config repository of your team. Later dirs override earlier ones.
+	pub shared_config_dirs: Option<Vec<String>>,
 	pub workspace: String,
 	pub shell: Option<Vec<String>>,
 	pub default_after_workon: Option<String>,
 	pub default_after_clone: Option<String>,
+	/// The token itself or `file:<path>` to read it from a file
 	pub github_token: Option<String>,
+	/// Command printing the token, run through the shell, e.g. `pass show github`
+	pub github_token_command: Option<String>,
+	/// GraphQL endpoint of GitHub Enterprise, e.g. https://github.example.com/api/graphql
+	pub github_api_url: Option<String>,
+	/// Host used in the ssh clone urls of imported repositories instead of the one GitHub reports, e.g. an alias from ~/.ssh/config
+	pub github_ssh_host: Option<String>,
+	pub ssh_key_files: Option<Vec<String>>,
+	pub gitlab_url: Option<String>,
+	pub gitlab_token: Option<String>,
+	pub gitea_url: Option<String>,
+	pub gitea_token: Option<String>,
+	pub bitbucket_url: Option<String>,
+	pub bitbucket_token: Option<String>,
+	pub import_tags: Option<ImportTags>,
+	pub retries: Option<u32>,
+	pub retry_backoff_ms: Option<u64>,
 }
 
 impl PersistedSettings {
 	pub fn example() -> PersistedSettings {
 		PersistedSettings {
+			version: Some(super::migrate::CURRENT_SCHEMA_VERSION),
+			shared_config_dirs: Some(vec!["~/workspace/team-fw-config".to_string()]),
 			workspace: "~/workspace".to_owned(),
 			default_after_workon: Some("echo default after workon".to_string()),
 			default_after_clone: Some("echo default after clone".to_string()),
 			shell: Some(vec!["/usr/bin/zsh".to_string(), "-c".to_string()]),
-			github_token: Some("githubtokensecret".to_string()),
+			github_token: None,
+			github_token_command: Some("pass show github".to_string()),
+			github_api_url: Some("https://github.example.com/api/graphql".to_string()),
+			github_ssh_host: Some("github-work".to_string()),
+			ssh_key_files: Some(vec!["~/.ssh/id_ed25519".to_string()]),
+			gitlab_url: Some("https://gitlab.example.com".to_string()),
+			gitlab_token: Some("gitlabtokensecret".to_string()),
+			gitea_url: Some("https://gitea.example.com".to_string()),
+			gitea_token: Some("giteatokensecret".to_string()),
+			bitbucket_url: Some("https://bitbucket.example.com".to_string()),
+			bitbucket_token: Some("bitbuckettokensecret".to_string()),
+			import_tags: Some(ImportTags {
+				topics: Some(BTreeMap::from([("backend".to_string(), "backend".to_string())])),
+				languages: Some(BTreeMap::from([("Rust".to_string(), "rust".to_string())])),
+				archived: Some("archived".to_string()),
+			}),
+			retries: Some(2),
+			retry_backoff_ms: Some(1000),
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use maplit::btreeset;
+
+	#[test]
+	fn test_import_tags_for_repository() {
+		let import_tags = PersistedSettings::example().import_tags.unwrap();
+		assert_eq!(
+			import_tags.tags_for(&["backend".to_owned(), "cli".to_owned()], Some("rust"), true),
+			btreeset!["archived".to_owned(), "backend".to_owned(), "rust".to_owned()]
+		);
+		assert!(import_tags.tags_for(&["cli".to_owned()], Some("Go"), false).is_empty());
+	}
+	#[test]
+	fn test_token_from_command() {
+		let shell = vec!["sh".to_owned(), "-c".to_owned()];
+		assert_eq!(token_from_command(&shell, "echo ' secret '").unwrap(), "secret");
+		assert!(token_from_command(&shell, "exit 1").is_err());
+		assert!(token_from_command(&shell, "true").is_err());
+	}
+	#[test]
+	fn test_token_from_file() {
+		let path = env::temp_dir().join(format!("fw-test-token-{}", std::process::id()));
+		fs::write(&path, "secret\n").unwrap();
+		let token = token_from_file(&path);
+		fs::remove_file(&path).unwrap();
+		assert_eq!(token.unwrap(), "secret");
+		assert!(token_from_file(&path).is_err());
+	}
+}
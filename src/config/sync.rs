@@ -0,0 +1,35 @@
+use super::Config;
+use super::path::fw_path;
+use crate::errors::AppError;
+use crate::git;
+
+use yansi::Paint;
+
+/// Keeps the config directory in sync with the git repository of the team: commits the changes to projects and tags
+/// made by `fw add`, `fw update`, `fw tag` etc., pulls the changes of others and pushes.
+pub fn sync(maybe_config: Result<Config, AppError>) -> Result<(), AppError> {
+	let paths = fw_path()?;
+	// a config that can't be read is not a reason not to pull a fixed one
+	let settings = maybe_config.map(|c| c.settings).unwrap_or_default();
+	let sync = git::sync_config_repository(&settings, &paths.base)?;
+
+	for file in &sync.committed {
+		println!("COMMITTED: {file}");
+	}
+	if !sync.has_remote {
+		println!("No remote origin in {}, only committed the changes", paths.base.display());
+		return Ok(());
+	}
+	for file in &sync.conflicts {
+		println!("{} {}", "CONFLICT:".red(), file);
+	}
+	if !sync.conflicts.is_empty() {
+		return Err(AppError::UserError(format!(
+			"Your changes conflict with the remote. Nothing was pulled or pushed, resolve the conflicts with `git pull --rebase` in {}",
+			paths.base.display()
+		)));
+	}
+	println!("PULLED: {} commit(s)", sync.pulled);
+	println!("PUSHED: {} commit(s)", sync.pushed);
+	Ok(())
+}
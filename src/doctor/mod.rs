@@ -0,0 +1,240 @@
+use crate::config::{self, Config, Duplicates, expand_path};
+use crate::errors::AppError;
+use crate::git::ssh_agent_running;
+
+use git2::Repository;
+use std::collections::BTreeMap;
+use std::env;
+use std::path::PathBuf;
+use yansi::Paint;
+
+#[derive(Debug, PartialEq, Eq)]
+struct Problem {
+	subject: String,
+	message: String,
+}
+
+impl Problem {
+	fn new(subject: &str, message: String) -> Problem {
+		Problem {
+			subject: subject.to_owned(),
+			message,
+		}
+	}
+}
+
+fn undefined_tags(config: &Config) -> Vec<Problem> {
+	let defined = config.settings.tags.clone().unwrap_or_default();
+	config
+		.projects
+		.values()
+		.flat_map(|project| {
+			project
+				.tags
+				.clone()
+				.unwrap_or_default()
+				.into_iter()
+				.filter(|tag| !defined.contains_key(tag))
+				.map(|tag| Problem::new(&project.name, format!("tag {tag} is not defined")))
+				.collect::<Vec<Problem>>()
+		})
+		.collect()
+}
+
+fn duplicates(duplicates: &Duplicates) -> Vec<Problem> {
+	let describe = |kind: &str, definitions: &BTreeMap<String, Vec<String>>| -> Vec<Problem> {
+		definitions
+			.iter()
+			.map(|(name, config_paths)| Problem::new(name, format!("{kind} is defined in more than one directory: {}", config_paths.join(", "))))
+			.collect()
+	};
+	let mut problems = describe("project", &duplicates.projects);
+	problems.extend(describe("tag", &duplicates.tags));
+	problems
+}
+
+fn missing_override_paths(config: &Config) -> Vec<Problem> {
+	config
+		.projects
+		.values()
+		.filter_map(|project| project.override_path.as_ref().map(|path| (project, path)))
+		.filter(|(_, path)| !expand_path(PathBuf::from(path)).exists())
+		.map(|(project, path)| Problem::new(&project.name, format!("override path {path} does not exist")))
+		.collect()
+}
+
+fn shared_paths(config: &Config) -> Vec<Problem> {
+	let mut by_path: BTreeMap<PathBuf, Vec<String>> = BTreeMap::new();
+	for project in config.projects.values() {
+		by_path.entry(config.actual_path_to_project(project)).or_default().push(project.name.clone());
+	}
+	by_path
+		.into_iter()
+		.filter(|(_, names)| names.len() > 1)
+		.map(|(path, names)| Problem::new(&names.join(", "), format!("projects share the path {}", path.display())))
+		.collect()
+}
+
+fn diverging_remotes(config: &Config) -> Vec<Problem> {
+	let mut problems = Vec::new();
+	for project in config.projects.values() {
+		let path = config.actual_path_to_project(project);
+		if !path.exists() {
+			continue;
+		}
+		let repository = match Repository::open(&path) {
+			Ok(repository) => repository,
+			Err(e) => {
+				problems.push(Problem::new(
+					&project.name,
+					format!("{} is not a git repository: {}", path.display(), e.message()),
+				));
+				continue;
+			}
+		};
+		let desired = project
+			.additional_remotes
+			.clone()
+			.unwrap_or_default()
+			.into_iter()
+			.map(|r| (r.name, r.git))
+			.chain(std::iter::once(("origin".to_owned(), project.git.clone())));
+		for (name, git) in desired {
+			match repository.find_remote(&name).ok().and_then(|r| r.url().map(ToOwned::to_owned)) {
+				Some(ref url) if *url == git => (),
+				Some(url) => problems.push(Problem::new(&project.name, format!("remote {name} points to {url} instead of {git}"))),
+				None => problems.push(Problem::new(&project.name, format!("remote {name} ({git}) is missing"))),
+			}
+		}
+	}
+	problems
+}
+
+fn is_executable_on_path(program: &str, path_var: Option<&str>) -> bool {
+	if program.contains('/') {
+		return expand_path(PathBuf::from(program)).is_file();
+	}
+	path_var
+		.map(|paths| env::split_paths(paths).any(|dir| dir.join(program).is_file()))
+		.unwrap_or(false)
+}
+
+fn shell_problems(shell: &[String], path_var: Option<&str>) -> Vec<Problem> {
+	match shell.first() {
+		None => vec![Problem::new("settings", "shell is empty".to_owned())],
+		Some(program) if !is_executable_on_path(program, path_var) => {
+			vec![Problem::new("settings", format!("shell {program} was not found"))]
+		}
+		Some(_) => vec![],
+	}
+}
+
+fn is_ssh_url(git: &str) -> bool {
+	git.starts_with("ssh://") || (!git.contains("://") && git.contains('@') && git.contains(':'))
+}
+
+fn ssh_problems(config: &Config) -> Vec<Problem> {
+	let key_files = config.settings.ssh_key_files.clone().unwrap_or_default();
+	let mut problems: Vec<Problem> = key_files
+		.iter()
+		.filter(|key_file| !expand_path(PathBuf::from(key_file)).exists())
+		.map(|key_file| Problem::new("settings", format!("ssh key file {key_file} does not exist")))
+		.collect();
+	let uses_ssh = config
+		.projects
+		.values()
+		.flat_map(|p| std::iter::once(p.git.clone()).chain(p.additional_remotes.clone().unwrap_or_default().into_iter().map(|r| r.git)))
+		.any(|git| is_ssh_url(&git));
+	if uses_ssh && key_files.is_empty() && !ssh_agent_running() {
+		problems.push(Problem::new(
+			"ssh",
+			"projects use ssh urls but no ssh-agent is running and no ssh_key_files are configured".to_owned(),
+		));
+	}
+	problems
+}
+
+/// Checks the config and the workspace for problems. Fails if there are any, so it can be used in CI.
+pub fn doctor(maybe_config: Result<Config, AppError>) -> Result<(), AppError> {
+	let config = maybe_config?;
+	let path_var = env::var("PATH").ok();
+
+	let mut problems = undefined_tags(&config);
+	problems.extend(duplicates(&config::find_duplicates()?));
+	problems.extend(missing_override_paths(&config));
+	problems.extend(shared_paths(&config));
+	problems.extend(diverging_remotes(&config));
+	problems.extend(shell_problems(&config.settings.get_shell_or_default(), path_var.as_deref()));
+	problems.extend(ssh_problems(&config));
+
+	for problem in &problems {
+		println!("{} {}: {}", "PROBLEM:".red(), problem.subject, problem.message);
+	}
+	if problems.is_empty() {
+		println!("No problems found");
+		Ok(())
+	} else {
+		Err(AppError::UserError(format!("{} problem(s) found", problems.len())))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::config::ConfigSources;
+	use crate::config::project::Project;
+	use crate::config::settings::Settings;
+	use std::collections::BTreeSet;
+
+	fn config(projects: Vec<Project>) -> Config {
+		Config {
+			projects: projects.into_iter().map(|p| (p.name.clone(), p)).collect(),
+			settings: Settings {
+				workspace: "/test".to_owned(),
+				tags: Some(BTreeMap::new()),
+				..Settings::default()
+			},
+			sources: ConfigSources::default(),
+		}
+	}
+
+	#[test]
+	fn test_undefined_tags() {
+		let project = Project {
+			name: "fw".to_owned(),
+			tags: Some(BTreeSet::from(["rust".to_owned()])),
+			..Project::example()
+		};
+		assert_eq!(
+			undefined_tags(&config(vec![project])),
+			vec![Problem::new("fw", "tag rust is not defined".to_owned())]
+		);
+	}
+	#[test]
+	fn test_shared_paths() {
+		let fw = Project {
+			name: "fw".to_owned(),
+			override_path: Some("/tmp/shared".to_owned()),
+			..Project::example()
+		};
+		let other = Project {
+			name: "other".to_owned(),
+			..fw.clone()
+		};
+		assert_eq!(shared_paths(&config(vec![fw, other])).len(), 1);
+	}
+	#[test]
+	fn test_shell_problems() {
+		assert!(shell_problems(&["/bin/sh".to_owned()], None).is_empty());
+		assert!(shell_problems(&["sh".to_owned()], Some("/bin")).is_empty());
+		assert_eq!(shell_problems(&["no-such-shell".to_owned()], Some("/bin")).len(), 1);
+		assert_eq!(shell_problems(&[], None).len(), 1);
+	}
+	#[test]
+	fn test_is_ssh_url() {
+		assert!(is_ssh_url("git@github.com:brocode/fw.git"));
+		assert!(is_ssh_url("ssh://git@github.com/brocode/fw.git"));
+		assert!(!is_ssh_url("https://github.com/brocode/fw.git"));
+		assert!(!is_ssh_url("/tmp/fw"));
+	}
+}
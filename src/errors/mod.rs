@@ -1,6 +1,7 @@
 use std::error::Error;
 use std::fmt;
 use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Debug)]
 pub enum AppError {
@@ -15,6 +16,12 @@ pub enum AppError {
 	TomlDeError(toml::de::Error),
 	WalkdirError(walkdir::Error),
 	ReqwestError(reqwest::Error),
+	/// Messages from the `errors` of a GraphQL response
+	GraphQlError(Vec<String>),
+	/// The api rate limit is used up until `reset` (unix time in seconds)
+	RateLimited {
+		reset: Option<u64>,
+	},
 }
 
 macro_rules! app_error_from {
@@ -51,6 +58,12 @@ impl fmt::Display for AppError {
 			AppError::TomlDeError(ref err) => write!(f, "toml read error: {err}"),
 			AppError::WalkdirError(ref err) => write!(f, "walkdir error: {err}"),
 			AppError::ReqwestError(ref err) => write!(f, "reqwest error: {err}"),
+			AppError::GraphQlError(ref messages) => write!(f, "GraphQL error: {}", messages.join("; ")),
+			AppError::RateLimited { reset: Some(reset) } => {
+				let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+				write!(f, "Rate limit exceeded, try again in {} minutes", reset.saturating_sub(now).div_ceil(60))
+			}
+			AppError::RateLimited { reset: None } => write!(f, "Rate limit exceeded, try again later"),
 		}
 	}
 }
@@ -59,7 +72,7 @@ impl Error for AppError {
 	fn cause(&self) -> Option<&dyn Error> {
 		match *self {
 			AppError::Io(ref err) => Some(err),
-			AppError::UserError(_) | AppError::RuntimeError(_) | AppError::InternalError(_) => None,
+			AppError::UserError(_) | AppError::RuntimeError(_) | AppError::InternalError(_) | AppError::GraphQlError(_) | AppError::RateLimited { .. } => None,
 			AppError::BadJson(ref err) => Some(err),
 			AppError::GitError(ref err) => Some(err),
 			AppError::Regex(ref err) => Some(err),
@@ -0,0 +1,257 @@
+// libgit2 supports neither partial clones (--filter) nor sparse checkouts. Projects using them are cloned and
+// updated with the git command line client instead. It gets the ssh key files and the github token of the settings,
+// reads the proxy from its config and the environment itself and is retried by the caller like libgit2.
+
+use crate::config::expand_path;
+use crate::config::settings::Settings;
+use crate::errors::AppError;
+use crate::ws;
+
+use git2::{ErrorClass, ErrorCode};
+
+use std::collections::BTreeSet;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use super::credentials::is_github_url;
+
+/// Passed to the credential helper in the environment so that it doesn't show up in the process list.
+const TOKEN_VARIABLE: &str = "FW_GIT_CLI_TOKEN";
+
+fn quote(arg: &str) -> String {
+	format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+/// `GIT_SSH_COMMAND` that offers the key files in addition to the ssh-agent.
+fn ssh_command(base: &str, key_files: &[PathBuf]) -> String {
+	key_files
+		.iter()
+		.fold(base.to_owned(), |command, key| format!("{} -i {}", command, quote(&key.to_string_lossy())))
+}
+
+/// `GIT_CONFIG_*` entries that add the token credential helper for the hosts after the `existing` ones of the user.
+fn credential_helper_config(existing: usize, github_hosts: &BTreeSet<String>) -> Vec<(String, String)> {
+	let mut envs: Vec<(String, String)> = vec![("GIT_CONFIG_COUNT".to_owned(), (existing + github_hosts.len()).to_string())];
+	for (index, host) in (existing..).zip(github_hosts) {
+		envs.push((format!("GIT_CONFIG_KEY_{index}"), format!("credential.https://{host}.helper")));
+		envs.push((
+			format!("GIT_CONFIG_VALUE_{index}"),
+			format!("!f() {{ echo username=x-access-token; echo \"password=${TOKEN_VARIABLE}\"; }}; f"),
+		));
+	}
+	envs
+}
+
+/// The environment that hands the credentials fw uses with libgit2 to git.
+fn credential_env(settings: &Settings, url: &str) -> Result<Vec<(String, String)>, AppError> {
+	let mut envs: Vec<(String, String)> = Vec::new();
+	if let Some(key_files) = &settings.ssh_key_files {
+		let key_files: Vec<PathBuf> = key_files.iter().map(|k| expand_path(PathBuf::from(k))).collect();
+		let base = env::var("GIT_SSH_COMMAND").unwrap_or_else(|_| "ssh".to_owned());
+		envs.push(("GIT_SSH_COMMAND".to_owned(), ssh_command(&base, &key_files)));
+	}
+	let github_hosts = ws::github_hosts(settings);
+	if is_github_url(url, &github_hosts)
+		&& settings.has_github_token()
+		&& let Some(token) = settings.resolve_github_token()?
+	{
+		// tried after the credential helpers of the git config
+		let existing = env::var("GIT_CONFIG_COUNT").ok().and_then(|count| count.parse().ok()).unwrap_or(0);
+		envs.extend(credential_helper_config(existing, &github_hosts));
+		envs.push((TOKEN_VARIABLE.to_owned(), token));
+	}
+	Ok(envs)
+}
+
+fn run_git(args: &[String], workdir: Option<&Path>) -> Result<(), AppError> {
+	run_git_with_env(args, workdir, &[])
+}
+
+fn run_git_with_env(args: &[String], workdir: Option<&Path>, envs: &[(String, String)]) -> Result<(), AppError> {
+	let mut command = Command::new("git");
+	command
+		.args(args)
+		.envs(envs.iter().map(|(key, value)| (key, value)))
+		// fail instead of waiting for a password prompt that can't be answered
+		.env("GIT_TERMINAL_PROMPT", "0")
+		.stdin(Stdio::null())
+		.stdout(Stdio::null())
+		.stderr(Stdio::piped());
+	if let Some(dir) = workdir {
+		command.current_dir(dir);
+	}
+	let output = command
+		.output()
+		.map_err(|e| AppError::RuntimeError(format!("Failed to run git (required for partial and sparse clones): {e}")))?;
+	if output.status.success() {
+		Ok(())
+	} else {
+		let stderr = String::from_utf8_lossy(&output.stderr);
+		Err(AppError::GitError(git2::Error::new(
+			error_code(&stderr),
+			ErrorClass::Net,
+			format!("git {} failed: {}", args.join(" "), stderr.trim()),
+		)))
+	}
+}
+
+/// The code of a failed git command like libgit2 would report it, so that it is retried like the libgit2 operations
+/// unless the credentials were rejected or the repository doesn't exist.
+fn error_code(stderr: &str) -> ErrorCode {
+	let stderr = stderr.to_lowercase();
+	if [
+		"authentication failed",
+		"permission denied",
+		"could not read username",
+		"could not read password",
+	]
+	.iter()
+	.any(|message| stderr.contains(message))
+	{
+		ErrorCode::Auth
+	} else if ["not found", "does not appear to be a git repository"]
+		.iter()
+		.any(|message| stderr.contains(message))
+	{
+		ErrorCode::NotFound
+	} else {
+		ErrorCode::GenericError
+	}
+}
+
+fn clone_args(url: &str, path: &Path, depth: Option<u32>, filter: Option<&str>, branch: Option<&str>, bare: bool, sparse: bool) -> Vec<String> {
+	let mut args: Vec<String> = vec!["clone".to_owned(), "--quiet".to_owned()];
+	if let Some(depth) = depth {
+		args.push(format!("--depth={depth}"));
+	}
+	if let Some(filter) = filter {
+		args.push(format!("--filter={filter}"));
+	}
+	if let Some(branch) = branch {
+		args.push(format!("--branch={branch}"));
+	}
+	if bare {
+		args.push("--bare".to_owned());
+	} else if sparse {
+		args.push("--sparse".to_owned());
+	}
+	args.push("--".to_owned());
+	args.push(url.to_owned());
+	args.push(path.to_string_lossy().to_string());
+	args
+}
+
+pub fn clone(
+	settings: &Settings,
+	url: &str,
+	path: &Path,
+	depth: Option<u32>,
+	filter: Option<&str>,
+	sparse_checkout: Option<&[String]>,
+	branch: Option<&str>,
+	bare: bool,
+) -> Result<(), AppError> {
+	run_git_with_env(
+		&clone_args(url, path, depth, filter, branch, bare, sparse_checkout.is_some()),
+		None,
+		&credential_env(settings, url)?,
+	)?;
+	match sparse_checkout {
+		Some(patterns) if !bare => {
+			let mut args: Vec<String> = vec!["sparse-checkout".to_owned(), "set".to_owned(), "--".to_owned()];
+			args.extend_from_slice(patterns);
+			run_git(&args, Some(path))
+		}
+		_ => Ok(()),
+	}
+}
+
+pub fn fetch(settings: &Settings, path: &Path, remote_name: &str, depth: Option<u32>) -> Result<(), AppError> {
+	let url = git2::Repository::open(path)?.find_remote(remote_name)?.url().unwrap_or_default().to_owned();
+	let mut args: Vec<String> = vec!["fetch".to_owned(), "--quiet".to_owned()];
+	if let Some(depth) = depth {
+		args.push(format!("--depth={depth}"));
+	}
+	args.push(remote_name.to_owned());
+	run_git_with_env(&args, Some(path), &credential_env(settings, &url)?)
+}
+
+/// Fast-forwards the checked out branch to its upstream. Respects the sparse checkout and fetches missing blobs on demand.
+pub fn fast_forward(path: &Path) -> Result<(), AppError> {
+	run_git(&["merge".to_owned(), "--ff-only".to_owned(), "--quiet".to_owned()], Some(path))
+}
+
+/// Rebases the checked out branch onto its upstream. A failed rebase is aborted so the repository is left as it was.
+pub fn rebase(path: &Path, autostash: bool) -> Result<(), AppError> {
+	let mut args: Vec<String> = vec!["rebase".to_owned(), "--quiet".to_owned()];
+	if autostash {
+		args.push("--autostash".to_owned());
+	}
+	run_git(&args, Some(path)).inspect_err(|_| {
+		let _ = run_git(&["rebase".to_owned(), "--abort".to_owned()], Some(path));
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_ssh_command_quotes_key_files() {
+		assert_eq!(
+			ssh_command("ssh", &[PathBuf::from("/home/me/.ssh/id_work"), PathBuf::from("/keys/bob's key")]),
+			"ssh -i '/home/me/.ssh/id_work' -i '/keys/bob'\\''s key'"
+		);
+	}
+
+	#[test]
+	fn test_credential_helper_is_appended_to_existing_config() {
+		let envs = credential_helper_config(2, &BTreeSet::from(["github.com".to_owned()]));
+		assert_eq!(envs[0], ("GIT_CONFIG_COUNT".to_owned(), "3".to_owned()));
+		assert_eq!(envs[1], ("GIT_CONFIG_KEY_2".to_owned(), "credential.https://github.com.helper".to_owned()));
+		assert_eq!(envs[2].0, "GIT_CONFIG_VALUE_2");
+		assert_eq!(envs.len(), 3);
+	}
+
+	#[test]
+	fn test_error_code_of_failed_git() {
+		assert_eq!(
+			error_code("fatal: Authentication failed for 'https://github.com/brocode/fw.git/'"),
+			ErrorCode::Auth
+		);
+		assert_eq!(error_code("git@github.com: Permission denied (publickey)."), ErrorCode::Auth);
+		assert_eq!(error_code("remote: Repository not found."), ErrorCode::NotFound);
+		assert_eq!(
+			error_code("fatal: unable to access 'https://github.com/brocode/fw.git/': Could not resolve host: github.com"),
+			ErrorCode::GenericError
+		);
+	}
+
+	#[test]
+	fn test_clone_args() {
+		let args = clone_args(
+			"git@github.com:brocode/fw.git",
+			Path::new("/ws/fw"),
+			Some(1),
+			Some("blob:none"),
+			Some("main"),
+			false,
+			true,
+		);
+		assert_eq!(
+			args,
+			vec![
+				"clone",
+				"--quiet",
+				"--depth=1",
+				"--filter=blob:none",
+				"--branch=main",
+				"--sparse",
+				"--",
+				"git@github.com:brocode/fw.git",
+				"/ws/fw"
+			]
+		);
+	}
+}
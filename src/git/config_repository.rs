@@ -0,0 +1,171 @@
+use crate::config::settings::Settings;
+use crate::errors::AppError;
+
+use git2::{BranchType, ErrorCode, IndexAddOption, PushOptions, Repository};
+use std::path::Path;
+
+use super::credentials::credential_callbacks;
+use super::update::{UpdateOutcome, fast_forward, rebase_onto_upstream};
+use super::update_remote;
+
+const CONFIG_REMOTE: &str = "origin";
+/// Only the definitions are synced. settings.toml is personal (workspace, shell, tokens) and never committed.
+const SYNCED_PATHS: [&str; 2] = ["projects", "tags"];
+
+/// What `fw config sync` did to the config repository.
+#[derive(Debug, Default)]
+pub struct ConfigSync {
+	/// Files changed by fw (or by hand) since the last sync and committed now
+	pub committed: Vec<String>,
+	/// Commits pulled from the remote
+	pub pulled: usize,
+	/// Commits pushed to the remote
+	pub pushed: usize,
+	/// Files changed locally and on the remote. Nothing was pulled or pushed if there are any.
+	pub conflicts: Vec<String>,
+	/// Whether there is a remote to sync with
+	pub has_remote: bool,
+}
+
+/// Stages all changes of the synced paths and commits them. Returns the changed files.
+fn commit_changes(repo: &Repository) -> Result<Vec<String>, AppError> {
+	let mut index = repo.index()?;
+	index.add_all(SYNCED_PATHS, IndexAddOption::DEFAULT, None)?;
+	// picks up deleted files
+	index.update_all(SYNCED_PATHS, None)?;
+	index.write()?;
+
+	let parent = match repo.head() {
+		Ok(head) => Some(head.peel_to_commit()?),
+		Err(e) if e.code() == ErrorCode::UnbornBranch => None,
+		Err(e) => return Err(AppError::GitError(e)),
+	};
+	let parent_tree = parent.as_ref().map(|p| p.tree()).transpose()?;
+	let changed: Vec<String> = repo
+		.diff_tree_to_index(parent_tree.as_ref(), Some(&index), None)?
+		.deltas()
+		.filter_map(|delta| {
+			delta
+				.new_file()
+				.path()
+				.or_else(|| delta.old_file().path())
+				.map(|p| p.to_string_lossy().to_string())
+		})
+		.collect();
+	if changed.is_empty() {
+		return Ok(changed);
+	}
+
+	let tree = repo.find_tree(index.write_tree()?)?;
+	let signature = repo.signature()?;
+	let message = format!("Update fw config\n\n{}\n", changed.join("\n"));
+	let parents: Vec<&git2::Commit<'_>> = parent.iter().collect();
+	repo.commit(Some("HEAD"), &signature, &signature, &message, &tree, &parents)?;
+	Ok(changed)
+}
+
+/// Commits the local changes of the config directory, pulls (rebasing local commits) and pushes to `origin`.
+/// A rebase with conflicts is aborted so the config stays readable; the conflicts are reported.
+pub fn sync_config_repository(settings: &Settings, path: &Path) -> Result<ConfigSync, AppError> {
+	let mut repo = Repository::open(path).map_err(|e| {
+		AppError::UserError(format!(
+			"{} is not a git repository ({}). Run `git init` there and add the shared repository as remote {}.",
+			path.display(),
+			e.message(),
+			CONFIG_REMOTE
+		))
+	})?;
+	let mut sync = ConfigSync {
+		committed: commit_changes(&repo)?,
+		..ConfigSync::default()
+	};
+
+	let mut remote = match repo.find_remote(CONFIG_REMOTE) {
+		Ok(remote) => remote,
+		Err(e) if e.code() == ErrorCode::NotFound => return Ok(sync),
+		Err(e) => return Err(AppError::GitError(e)),
+	};
+	sync.has_remote = true;
+	update_remote(settings, &mut remote, None)?;
+	drop(remote);
+
+	let branch_name = repo
+		.find_reference("HEAD")?
+		.symbolic_target()
+		.and_then(|target| target.strip_prefix("refs/heads/"))
+		.map(ToOwned::to_owned)
+		.ok_or_else(|| AppError::UserError(format!("The config repository in {} has no branch checked out", path.display())))?;
+	let upstream_name = format!("{CONFIG_REMOTE}/{branch_name}");
+	let upstream_oid = match repo.find_branch(&upstream_name, BranchType::Remote) {
+		Ok(upstream) => upstream.get().target(),
+		Err(e) if e.code() == ErrorCode::NotFound => None,
+		Err(e) => return Err(AppError::GitError(e)),
+	};
+	let local_oid = match repo.refname_to_id(&format!("refs/heads/{branch_name}")) {
+		Ok(local_oid) => local_oid,
+		// a new machine: nothing committed yet, so the config dir was empty
+		Err(e) if e.code() == ErrorCode::NotFound => {
+			if let Some(upstream_oid) = upstream_oid {
+				repo.reference(&format!("refs/heads/{branch_name}"), upstream_oid, false, "fw config sync")?;
+				repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))?;
+				repo.find_branch(&branch_name, BranchType::Local)?.set_upstream(Some(&upstream_name))?;
+				sync.pulled = count_commits(&repo, upstream_oid)?;
+			}
+			return Ok(sync);
+		}
+		Err(e) => return Err(AppError::GitError(e)),
+	};
+	if upstream_oid.is_some() {
+		// the first sync of a clone
+		repo.find_branch(&branch_name, BranchType::Local)?.set_upstream(Some(&upstream_name))?;
+	}
+
+	if let Some(upstream_oid) = upstream_oid {
+		let (ahead, behind) = repo.graph_ahead_behind(local_oid, upstream_oid)?;
+		sync.pulled = behind;
+		sync.pushed = ahead;
+		if behind > 0 && ahead == 0 {
+			if fast_forward(&repo, upstream_oid)? == UpdateOutcome::DirtyTree {
+				return Err(AppError::UserError(format!("Untracked files in {} block the update", path.display())));
+			}
+		} else if behind > 0 {
+			sync.conflicts = rebase_onto_upstream(&mut repo)?;
+			if !sync.conflicts.is_empty() {
+				sync.pulled = 0;
+				sync.pushed = 0;
+				return Ok(sync);
+			}
+		}
+	} else {
+		sync.pushed = count_commits(&repo, local_oid)?;
+	}
+
+	if sync.pushed > 0 {
+		let mut callbacks = credential_callbacks(settings);
+		callbacks.push_update_reference(|reference, status| match status {
+			Some(message) => Err(git2::Error::from_str(&format!("Push of {reference} was rejected: {message}"))),
+			None => Ok(()),
+		});
+		let mut options = PushOptions::new();
+		options.remote_callbacks(callbacks);
+		let refspec = format!("refs/heads/{branch_name}:refs/heads/{branch_name}");
+		repo.find_remote(CONFIG_REMOTE)?.push(&[refspec.as_str()], Some(&mut options))?;
+		if upstream_oid.is_none() {
+			// the remote-tracking branch is only created by the next fetch
+			repo.reference(
+				&format!("refs/remotes/{upstream_name}"),
+				repo.head()?.target().ok_or(AppError::InternalError("HEAD has no target"))?,
+				true,
+				"fw config sync",
+			)?;
+			repo.find_branch(&branch_name, BranchType::Local)?.set_upstream(Some(&upstream_name))?;
+		}
+	}
+	Ok(sync)
+}
+
+fn count_commits(repo: &Repository, oid: git2::Oid) -> Result<usize, AppError> {
+	let mut walk = repo.revwalk()?;
+	walk.push(oid)?;
+	Ok(walk.count())
+}
@@ -0,0 +1,221 @@
+use crate::config::expand_path;
+use crate::config::settings::Settings;
+use crate::ws;
+
+use git2::{Cred, CredentialType, RemoteCallbacks};
+
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+#[cfg(unix)]
+use std::os::unix::fs::FileTypeExt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CredentialSource {
+	SshAgent,
+	SshKeyFile(usize),
+	GithubToken,
+	CredentialHelper,
+	Username,
+	Default,
+}
+
+impl CredentialSource {
+	fn describe(&self) -> &'static str {
+		match self {
+			CredentialSource::SshAgent => "ssh-agent",
+			CredentialSource::SshKeyFile(_) => "ssh key file",
+			CredentialSource::GithubToken => "github token",
+			CredentialSource::CredentialHelper => "git credential helper",
+			CredentialSource::Username => "username",
+			CredentialSource::Default => "default credentials",
+		}
+	}
+}
+
+/// The credential sources to try for the given request, in order of preference.
+fn credential_sources(
+	url: &str,
+	allowed_types: CredentialType,
+	agent_running: bool,
+	key_files: usize,
+	has_github_token: bool,
+	github_hosts: &BTreeSet<String>,
+) -> Vec<CredentialSource> {
+	let mut sources = Vec::new();
+	if allowed_types.contains(CredentialType::USERNAME) {
+		sources.push(CredentialSource::Username);
+	}
+	if allowed_types.contains(CredentialType::SSH_KEY) {
+		if agent_running {
+			sources.push(CredentialSource::SshAgent);
+		}
+		sources.extend((0..key_files).map(CredentialSource::SshKeyFile));
+	}
+	if allowed_types.contains(CredentialType::USER_PASS_PLAINTEXT) {
+		if has_github_token && is_github_url(url, github_hosts) {
+			sources.push(CredentialSource::GithubToken);
+		}
+		sources.push(CredentialSource::CredentialHelper);
+	}
+	if allowed_types.contains(CredentialType::DEFAULT) {
+		sources.push(CredentialSource::Default);
+	}
+	sources
+}
+
+/// Whether the url is an https url of github.com or the GitHub Enterprise host, see [`ws::github_hosts`].
+pub(super) fn is_github_url(url: &str, github_hosts: &BTreeSet<String>) -> bool {
+	(url.starts_with("https://") || url.starts_with("http://")) && ws::github::repository_host(url).is_some_and(|host| github_hosts.contains(host))
+}
+
+fn credential_from(
+	source: CredentialSource,
+	url: &str,
+	username: &str,
+	username_from_url: Option<&str>,
+	key_files: &[PathBuf],
+	github_token: Option<&str>,
+) -> Result<Cred, git2::Error> {
+	match source {
+		CredentialSource::SshAgent => Cred::ssh_key_from_agent(username),
+		CredentialSource::SshKeyFile(index) => Cred::ssh_key(username, None, &key_files[index], None),
+		CredentialSource::GithubToken => Cred::userpass_plaintext(username_from_url.unwrap_or("x-access-token"), github_token.unwrap_or_default()),
+		CredentialSource::CredentialHelper => Cred::credential_helper(&git2::Config::open_default()?, url, username_from_url),
+		CredentialSource::Username => Cred::username(username),
+		CredentialSource::Default => Cred::default(),
+	}
+}
+
+/// Callbacks that try ssh-agent, configured key files, the github token, git credential helpers and default credentials
+/// one after another. Every source is only tried once so that a wrong credential fails the operation instead of looping forever.
+pub fn credential_callbacks(settings: &Settings) -> RemoteCallbacks<'static> {
+	let key_files: Vec<PathBuf> = settings
+		.ssh_key_files
+		.clone()
+		.unwrap_or_default()
+		.into_iter()
+		.map(|k| expand_path(PathBuf::from(k)))
+		.collect();
+	let has_github_token = settings.has_github_token();
+	let github_hosts = ws::github_hosts(settings);
+	// resolved on first use because the token command may prompt or the file may be missing
+	let token_settings = settings.clone();
+	let agent_running = ssh_agent_running();
+	let mut tried: Vec<CredentialSource> = Vec::new();
+
+	let mut remote_callbacks = RemoteCallbacks::new();
+	remote_callbacks.credentials(move |url, username_from_url, allowed_types| {
+		let username = username_from_url.unwrap_or("git");
+		let sources = credential_sources(url, allowed_types, agent_running, key_files.len(), has_github_token, &github_hosts);
+		for source in sources {
+			if tried.contains(&source) {
+				continue;
+			}
+			tried.push(source);
+			let token = match source {
+				CredentialSource::GithubToken => token_settings
+					.resolve_github_token()
+					.inspect_err(|e| eprintln!("Could not resolve the github token: {e}"))
+					.ok()
+					.flatten(),
+				_ => None,
+			};
+			if source == CredentialSource::GithubToken && token.is_none() {
+				continue;
+			}
+			if let Ok(cred) = credential_from(source, url, username, username_from_url, &key_files, token.as_deref()) {
+				return Ok(cred);
+			}
+		}
+		let tried_sources: Vec<&str> = tried.iter().map(CredentialSource::describe).collect();
+		Err(git2::Error::from_str(&format!(
+			"No working credentials for {url} (tried: {}). Add your key to the ssh-agent, configure ssh_key_files or a github_token.",
+			if tried_sources.is_empty() {
+				"nothing".to_owned()
+			} else {
+				tried_sources.join(", ")
+			}
+		)))
+	});
+	remote_callbacks
+}
+
+pub fn ssh_agent_running() -> bool {
+	match std::env::var("SSH_AUTH_SOCK") {
+		Ok(auth_socket) => is_socket(&auth_socket),
+		Err(_) => false,
+	}
+}
+
+#[cfg(unix)]
+fn is_socket(path: &str) -> bool {
+	std::fs::metadata(path).map(|m| m.file_type().is_socket()).unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_socket(_: &str) -> bool {
+	false
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn hosts() -> BTreeSet<String> {
+		BTreeSet::from(["github.com".to_owned()])
+	}
+
+	#[test]
+	fn test_ssh_sources_in_order() {
+		let sources = credential_sources("git@github.com:brocode/fw.git", CredentialType::SSH_KEY, true, 2, true, &hosts());
+		assert_eq!(
+			sources,
+			vec![CredentialSource::SshAgent, CredentialSource::SshKeyFile(0), CredentialSource::SshKeyFile(1)]
+		);
+	}
+	#[test]
+	fn test_ssh_sources_without_agent() {
+		let sources = credential_sources("git@github.com:brocode/fw.git", CredentialType::SSH_KEY, false, 0, false, &hosts());
+		assert_eq!(sources, vec![]);
+	}
+	#[test]
+	fn test_https_github_uses_token_first() {
+		let sources = credential_sources(
+			"https://github.com/brocode/fw.git",
+			CredentialType::USER_PASS_PLAINTEXT,
+			true,
+			1,
+			true,
+			&hosts(),
+		);
+		assert_eq!(sources, vec![CredentialSource::GithubToken, CredentialSource::CredentialHelper]);
+	}
+	#[test]
+	fn test_https_other_host_does_not_leak_github_token() {
+		let sources = credential_sources(
+			"https://gitlab.com/brocode/fw.git",
+			CredentialType::USER_PASS_PLAINTEXT,
+			true,
+			1,
+			true,
+			&hosts(),
+		);
+		assert_eq!(sources, vec![CredentialSource::CredentialHelper]);
+	}
+	#[test]
+	fn test_https_github_enterprise_uses_token() {
+		let hosts = BTreeSet::from(["github.example.com".to_owned()]);
+		let sources = credential_sources(
+			"https://github.example.com/brocode/fw.git",
+			CredentialType::USER_PASS_PLAINTEXT,
+			true,
+			1,
+			true,
+			&hosts,
+		);
+		assert_eq!(sources, vec![CredentialSource::GithubToken, CredentialSource::CredentialHelper]);
+		let sources = credential_sources("https://github.com/brocode/fw.git", CredentialType::USER_PASS_PLAINTEXT, true, 1, true, &hosts);
+		assert_eq!(sources, vec![CredentialSource::CredentialHelper]);
+	}
+}
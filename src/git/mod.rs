@@ -1,15 +1,38 @@
-use crate::config::{Config, project::Project};
+use crate::config::{
+	Config,
+	project::{Project, Submodules, UpdateStrategy},
+	settings::Settings,
+};
 use crate::errors::AppError;
 
+mod cli;
+mod config_repository;
+mod credentials;
+mod submodules;
+mod update;
+mod worktrees;
+pub use config_repository::sync_config_repository;
+use credentials::credential_callbacks;
+pub use credentials::ssh_agent_running;
+use serde::Serialize;
+use submodules::update_submodules_recursive;
+use update::update_head;
+pub use update::{UpdateOutcome, unsaved_work};
+use worktrees::{checked_out_in_worktree, create_missing_worktrees, existing_worktree_paths};
+
 use crate::spawn::spawn_maybe;
 use crate::util::random_color;
 
 use git2::build::RepoBuilder;
-use git2::{AutotagOption, Branch, Direction, FetchOptions, MergeAnalysis, ProxyOptions, Remote, RemoteCallbacks, RemoteUpdateFlags, Repository};
+use git2::{
+	AutotagOption, BranchType, Direction, ErrorClass, ErrorCode, FetchOptions, ProxyOptions, Remote, RemoteUpdateFlags, Repository, RepositoryInitOptions,
+};
 
 use std::borrow::ToOwned;
 
 use std::path::Path;
+use std::thread;
+use std::time::Duration;
 
 pub fn repo_name_from_url(url: &str) -> Result<&str, AppError> {
 	let last_fragment = url.rsplit('/').next().ok_or_else(|| {
@@ -27,53 +50,126 @@ pub fn repo_name_from_url(url: &str) -> Result<&str, AppError> {
 	})
 }
 
-fn agent_callbacks() -> git2::RemoteCallbacks<'static> {
-	let mut remote_callbacks = RemoteCallbacks::new();
-	remote_callbacks.credentials(move |_url, username_from_url, _allowed_types| git2::Cred::ssh_key_from_agent(username_from_url.unwrap_or("git")));
-	remote_callbacks
+/// How a project is cloned and fetched.
+struct CloneOptions {
+	depth: Option<u32>,
+	filter: Option<String>,
+	sparse_checkout: Option<Vec<String>>,
 }
 
-fn agent_fetch_options() -> git2::FetchOptions<'static> {
-	let remote_callbacks = agent_callbacks();
+impl CloneOptions {
+	fn for_project(config: &Config, project: &Project) -> CloneOptions {
+		CloneOptions {
+			depth: config.resolve_clone_depth(project),
+			filter: config.resolve_clone_filter(project),
+			sparse_checkout: config.resolve_sparse_checkout(project),
+		}
+	}
+
+	fn requires_git_cli(&self) -> bool {
+		self.filter.is_some() || self.sparse_checkout.is_some()
+	}
+}
+
+fn fetch_options(settings: &Settings, depth: Option<u32>) -> git2::FetchOptions<'static> {
+	let remote_callbacks = credential_callbacks(settings);
 	let mut proxy_options = ProxyOptions::new();
 	proxy_options.auto();
 	let mut fetch_options = FetchOptions::new();
 	fetch_options.remote_callbacks(remote_callbacks);
 	fetch_options.proxy_options(proxy_options);
+	if let Some(depth) = depth {
+		fetch_options.depth(depth.try_into().unwrap_or(i32::MAX));
+	}
 
 	fetch_options
 }
 
-fn builder() -> RepoBuilder<'static> {
-	let options = agent_fetch_options();
+fn builder(settings: &Settings, depth: Option<u32>) -> RepoBuilder<'static> {
+	let options = fetch_options(settings, depth);
 	let mut repo_builder = RepoBuilder::new();
 	repo_builder.fetch_options(options);
 	repo_builder
 }
 
-fn update_remote(remote: &mut Remote<'_>) -> Result<(), AppError> {
-	let remote_callbacks = agent_callbacks();
+fn update_remote(settings: &Settings, remote: &mut Remote<'_>, depth: Option<u32>) -> Result<(), AppError> {
+	let remote_callbacks = credential_callbacks(settings);
 	let mut proxy_options = ProxyOptions::new();
 	proxy_options.auto();
 	remote
 		.connect_auth(Direction::Fetch, Some(remote_callbacks), Some(proxy_options))
 		.map_err(AppError::GitError)?;
-	let mut options = agent_fetch_options();
+	let mut options = fetch_options(settings, depth);
 	remote.download::<String>(&[], Some(&mut options)).map_err(AppError::GitError)?;
 	remote.disconnect()?;
 	remote.update_tips(None, RemoteUpdateFlags::UPDATE_FETCHHEAD, AutotagOption::Unspecified, None)?;
 	Ok(())
 }
 
-pub fn update_project_remotes(project: &Project, path: &Path, ff_merge: bool) -> Result<(), AppError> {
-	let local: Repository = Repository::open(path).map_err(AppError::GitError)?;
-	for desired_remote in project.additional_remotes.clone().unwrap_or_default().into_iter().chain(
-		vec![crate::config::project::Remote {
+/// Whether the git error may go away on its own: network, http and ssh failures except rejected credentials and
+/// missing repositories. libgit2 reports refused connections as os errors.
+fn is_transient(error: &git2::Error) -> bool {
+	matches!(error.class(), ErrorClass::Net | ErrorClass::Http | ErrorClass::Ssh | ErrorClass::Os)
+		&& !matches!(error.code(), ErrorCode::Auth | ErrorCode::NotFound)
+}
+
+/// Runs the operation and retries it with exponential backoff if it fails with a transient git error (e.g. a network hiccup).
+fn with_retries<T, F>(retries: u32, backoff: Duration, mut operation: F) -> Result<T, AppError>
+where
+	F: FnMut() -> Result<T, AppError>,
+{
+	let mut attempt: u32 = 0;
+	loop {
+		match operation() {
+			Err(AppError::GitError(ref e)) if attempt < retries && is_transient(e) => {
+				thread::sleep(backoff.saturating_mul(2u32.saturating_pow(attempt)));
+				attempt += 1;
+			}
+			result => return result,
+		}
+	}
+}
+
+/// What happened to the checkout of a project and to each of its worktrees during sync.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectUpdate {
+	/// Updated if the checkout or one of the worktrees was updated
+	pub outcome: UpdateOutcome,
+	pub worktrees: Vec<WorktreeUpdate>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct WorktreeUpdate {
+	pub path: String,
+	pub outcome: UpdateOutcome,
+}
+
+/// Updates all remotes of the project. Remotes that are not part of the project config are deleted if `prune` is set.
+/// Afterwards the checked out branch (and the declared worktrees) are updated using the strategy.
+pub fn update_project_remotes(config: &Config, project: &Project, path: &Path, strategy: UpdateStrategy, prune: bool) -> Result<ProjectUpdate, AppError> {
+	let retries = config.resolve_retries(project);
+	let backoff = config.resolve_retry_backoff(project);
+	let clone_options = CloneOptions::for_project(config, project);
+	let mut local: Repository = Repository::open(path).map_err(AppError::GitError)?;
+	let desired_remotes: Vec<crate::config::project::Remote> = project
+		.additional_remotes
+		.clone()
+		.unwrap_or_default()
+		.into_iter()
+		.chain(vec![crate::config::project::Remote {
 			name: "origin".to_string(),
 			git: project.git.to_owned(),
-		}]
-		.into_iter(),
-	) {
+		}])
+		.collect();
+
+	if prune {
+		let existing_remotes: Vec<String> = local.remotes()?.iter().flatten().map(ToOwned::to_owned).collect();
+		for stale_remote in existing_remotes.iter().filter(|r| !desired_remotes.iter().any(|d| &d.name == *r)) {
+			local.remote_delete(stale_remote)?;
+		}
+	}
+
+	for desired_remote in desired_remotes {
 		let remote = local
 			.find_remote(&desired_remote.name)
 			.or_else(|_| local.remote(&desired_remote.name, &desired_remote.git))?;
@@ -86,58 +182,179 @@ pub fn update_project_remotes(project: &Project, path: &Path, ff_merge: bool) ->
 			}
 		};
 
-		update_remote(&mut remote)?;
+		// no depth here: without one git and libgit2 only fetch the commits above the shallow boundary, so a shallow clone
+		// stays shallow. A depth would move the boundary to the new upstream commits, cut the checked out commit off from
+		// them and prevent fast-forwards.
+		if clone_options.requires_git_cli() {
+			with_retries(retries, backoff, || cli::fetch(&config.settings, path, &desired_remote.name, None))?;
+		} else {
+			with_retries(retries, backoff, || update_remote(&config.settings, &mut remote, None))?;
+		}
 	}
 
-	if ff_merge {
-		// error does not matter. fast forward not possible
-		let _ = fast_forward_merge(&local);
-	}
+	create_missing_worktrees(config, &local, project, path)?;
 
-	Ok(())
+	let mut outcome = update_head(&mut local, path, strategy, clone_options.requires_git_cli())?;
+	if strategy != UpdateStrategy::FetchOnly {
+		// a branch that is not checked out can only be fast-forwarded. errors do not matter, fast forward not possible
+		let branch_fast_forwarded = config
+			.resolve_branch(project)
+			.map(|branch| fast_forward_branch(&local, &branch).unwrap_or(false))
+			.unwrap_or(false);
+		if branch_fast_forwarded && outcome == UpdateOutcome::UpToDate {
+			outcome = UpdateOutcome::FastForwarded;
+		}
+	}
+	let mut worktrees: Vec<WorktreeUpdate> = Vec::new();
+	for worktree_path in existing_worktree_paths(&local, project, path) {
+		let mut worktree = Repository::open(&worktree_path)?;
+		let worktree_outcome = update_head(&mut worktree, &worktree_path, strategy, clone_options.requires_git_cli())?;
+		if worktree_outcome.is_updated() && outcome == UpdateOutcome::UpToDate {
+			outcome = worktree_outcome;
+		}
+		update_submodules(config, project, &worktree)?;
+		worktrees.push(WorktreeUpdate {
+			path: worktree_path.to_string_lossy().to_string(),
+			outcome: worktree_outcome,
+		});
+	}
+	update_submodules(config, project, &local)?;
+	Ok(ProjectUpdate { outcome, worktrees })
 }
 
-fn fast_forward_merge(local: &Repository) -> Result<(), AppError> {
-	let head_ref = local.head()?;
-	if head_ref.is_branch() {
-		let branch = Branch::wrap(head_ref);
-		let upstream = branch.upstream()?;
-		let upstream_commit = local.reference_to_annotated_commit(upstream.get())?;
+fn update_submodules(config: &Config, project: &Project, repository: &Repository) -> Result<(), AppError> {
+	if project.submodules == Some(Submodules::Recursive) && !repository.is_bare() {
+		with_retries(config.resolve_retries(project), config.resolve_retry_backoff(project), || {
+			update_submodules_recursive(&config.settings, repository)
+		})
+	} else {
+		Ok(())
+	}
+}
 
-		let (analysis_result, _) = local.merge_analysis(&[&upstream_commit])?;
-		if MergeAnalysis::is_fast_forward(&analysis_result) {
-			let target_id = upstream_commit.id();
-			local.checkout_tree(&local.find_object(upstream_commit.id(), None)?, None)?;
-			local.head()?.set_target(target_id, "fw fast-forward")?;
+/// Fast-forwards a local branch that is not checked out to its counterpart on origin. Creates the local branch if it does not exist.
+fn fast_forward_branch(local: &Repository, branch_name: &str) -> Result<bool, AppError> {
+	let upstream_name = format!("origin/{branch_name}");
+	let upstream = local.find_branch(&upstream_name, BranchType::Remote)?;
+	let upstream_oid = upstream
+		.get()
+		.target()
+		.ok_or_else(|| AppError::RuntimeError(format!("Remote branch {upstream_name} has no target")))?;
+	match local.find_branch(branch_name, BranchType::Local) {
+		Ok(mut branch) => {
+			let local_oid = branch
+				.get()
+				.target()
+				.ok_or_else(|| AppError::RuntimeError(format!("Branch {branch_name} has no target")))?;
+			// a checked out branch is handled by update_head
+			if branch.is_head() || checked_out_in_worktree(local, branch_name)? || local_oid == upstream_oid || !local.graph_descendant_of(upstream_oid, local_oid)? {
+				Ok(false)
+			} else {
+				branch.get_mut().set_target(upstream_oid, "fw fast-forward")?;
+				Ok(true)
+			}
+		}
+		Err(_) => {
+			let mut branch = local.branch(branch_name, &local.find_commit(upstream_oid)?, false)?;
+			branch.set_upstream(Some(&upstream_name))?;
+			Ok(true)
 		}
 	}
-	Ok(())
 }
 
 pub fn clone_project(config: &Config, project: &Project, path: &Path) -> Result<(), AppError> {
-	let shell = config.settings.get_shell_or_default();
-	let mut repo_builder = builder();
-	repo_builder
-		.bare(project.bare.unwrap_or_default())
-		.clone(project.git.as_str(), path)
-		.map_err(AppError::GitError)
-		.and_then(|repo| init_additional_remotes(project, repo))
-		.and_then(|_| {
-			let after_clone = config.resolve_after_clone(project);
-			if !after_clone.is_empty() {
-				spawn_maybe(&shell, &after_clone.join(" && "), path, &project.name, random_color())
-					.map_err(|error| AppError::UserError(format!("Post-clone hook failed (nonzero exit code). Cause: {error:?}")))
-			} else {
-				Ok(())
+	let existed = path.exists();
+	let branch = config.resolve_branch(project);
+	let clone_options = CloneOptions::for_project(config, project);
+	with_retries(config.resolve_retries(project), config.resolve_retry_backoff(project), || {
+		let cloned = if clone_options.requires_git_cli() {
+			cli::clone(
+				&config.settings,
+				&project.git,
+				path,
+				clone_options.depth,
+				clone_options.filter.as_deref(),
+				clone_options.sparse_checkout.as_deref(),
+				branch.as_deref(),
+				project.bare.unwrap_or_default(),
+			)
+			.and_then(|_| Repository::open(path).map_err(AppError::GitError))
+		} else {
+			let mut repo_builder = builder(&config.settings, clone_options.depth);
+			if let Some(ref branch) = branch {
+				repo_builder.branch(branch);
 			}
-		})
+			repo_builder
+				.bare(project.bare.unwrap_or_default())
+				.clone(project.git.as_str(), path)
+				.map_err(AppError::GitError)
+		};
+		cloned
+			.and_then(|repo| init_additional_remotes(&config.settings, project, repo, path, &clone_options))
+			.inspect_err(|_| {
+				if !existed {
+					remove_partial_clone(path)
+				}
+			})
+	})
+	.and_then(|_| {
+		let local = Repository::open(path)?;
+		update_submodules(config, project, &local)?;
+		create_missing_worktrees(config, &local, project, path)?;
+		for worktree_path in existing_worktree_paths(&local, project, path) {
+			update_submodules(config, project, &Repository::open(worktree_path)?)?;
+		}
+		Ok(())
+	})
+	.and_then(|_| run_after_clone(config, project, path))
+}
+
+/// Creates an empty repository for a new project with the project git url as origin and runs the after clone hooks.
+pub fn init_project(config: &Config, project: &Project, path: &Path) -> Result<(), AppError> {
+	let mut init_options = RepositoryInitOptions::new();
+	if let Some(branch) = config.resolve_branch(project) {
+		init_options.initial_head(&branch);
+	}
+	let repository = Repository::init_opts(path, &init_options)?;
+	repository.remote("origin", &project.git)?;
+	for remote in project.additional_remotes.clone().unwrap_or_default() {
+		repository.remote(&remote.name, &remote.git)?;
+	}
+	run_after_clone(config, project, path)
 }
 
-fn init_additional_remotes(project: &Project, repository: Repository) -> Result<(), AppError> {
+fn run_after_clone(config: &Config, project: &Project, path: &Path) -> Result<(), AppError> {
+	let after_clone = config.resolve_after_clone(project);
+	if !after_clone.is_empty() {
+		spawn_maybe(
+			&config.settings.get_shell_or_default(),
+			&after_clone.join(" && "),
+			path,
+			&project.name,
+			random_color(),
+		)
+		.map_err(|error| AppError::UserError(format!("Post-clone hook failed (nonzero exit code). Cause: {error:?}")))
+	} else {
+		Ok(())
+	}
+}
+
+fn remove_partial_clone(path: &Path) {
+	if path.exists() {
+		// best effort. a leftover directory would be treated as an existing project by the next sync
+		let _ = std::fs::remove_dir_all(path);
+	}
+}
+
+fn init_additional_remotes(settings: &Settings, project: &Project, repository: Repository, path: &Path, clone_options: &CloneOptions) -> Result<(), AppError> {
 	if let Some(additional_remotes) = &project.additional_remotes {
 		for remote in additional_remotes {
 			let mut git_remote = repository.remote(&remote.name, &remote.git)?;
-			update_remote(&mut git_remote)?;
+			if clone_options.requires_git_cli() {
+				cli::fetch(settings, path, &remote.name, clone_options.depth)?;
+			} else {
+				update_remote(settings, &mut git_remote, clone_options.depth)?;
+			}
 		}
 	}
 	Ok(())
@@ -147,6 +364,45 @@ fn init_additional_remotes(project: &Project, repository: Repository) -> Result<
 mod tests {
 	use super::*;
 
+	#[test]
+	fn test_with_retries_retries_git_errors() {
+		for class in [ErrorClass::Net, ErrorClass::Os] {
+			let mut attempts = 0;
+			let result: Result<(), AppError> = with_retries(2, Duration::ZERO, || {
+				attempts += 1;
+				Err(AppError::GitError(git2::Error::new(ErrorCode::GenericError, class, "network hiccup")))
+			});
+			assert!(result.is_err());
+			assert_eq!(attempts, 3);
+		}
+	}
+	#[test]
+	fn test_with_retries_does_not_retry_permanent_git_errors() {
+		for error in [
+			git2::Error::new(ErrorCode::Auth, ErrorClass::Ssh, "authentication failed"),
+			git2::Error::new(ErrorCode::NotFound, ErrorClass::Http, "repository not found"),
+			git2::Error::from_str("No working credentials"),
+		] {
+			let mut attempts = 0;
+			let mut error = Some(error);
+			let result: Result<(), AppError> = with_retries(2, Duration::ZERO, || {
+				attempts += 1;
+				Err(AppError::GitError(error.take().unwrap()))
+			});
+			assert!(result.is_err());
+			assert_eq!(attempts, 1);
+		}
+	}
+	#[test]
+	fn test_with_retries_does_not_retry_other_errors() {
+		let mut attempts = 0;
+		let result: Result<(), AppError> = with_retries(2, Duration::ZERO, || {
+			attempts += 1;
+			Err(AppError::UserError("hook failed".to_owned()))
+		});
+		assert!(result.is_err());
+		assert_eq!(attempts, 1);
+	}
 	#[test]
 	fn test_repo_name_from_url() {
 		let https_url = "https://github.com/mriehl/fw";
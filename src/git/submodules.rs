@@ -0,0 +1,23 @@
+use crate::config::settings::Settings;
+use crate::errors::AppError;
+
+use git2::{Repository, SubmoduleUpdateOptions};
+
+use super::fetch_options;
+
+/// Initializes and updates all submodules, including the submodules of submodules.
+pub fn update_submodules_recursive(settings: &Settings, repository: &Repository) -> Result<(), AppError> {
+	for mut submodule in repository.submodules()? {
+		let mut options = SubmoduleUpdateOptions::new();
+		options.fetch(fetch_options(settings, None));
+		submodule.update(true, Some(&mut options)).map_err(|e| {
+			AppError::GitError(git2::Error::from_str(&format!(
+				"Failed to update submodule {}: {}",
+				submodule.name().unwrap_or_else(|| submodule.path().to_str().unwrap_or_default()),
+				e.message()
+			)))
+		})?;
+		update_submodules_recursive(settings, &submodule.open()?)?;
+	}
+	Ok(())
+}
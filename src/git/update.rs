@@ -0,0 +1,313 @@
+use crate::config::project::UpdateStrategy;
+use crate::errors::AppError;
+
+use git2::{Branch, BranchType, ErrorCode, Rebase, RebaseOptions, Repository, Signature, StatusOptions};
+use serde::Serialize;
+
+use std::path::Path;
+
+use super::cli;
+
+/// What happened to the checked out branch of a project during sync.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum UpdateOutcome {
+	FastForwarded,
+	Rebased,
+	/// Rebased with the local changes stashed around it
+	AutostashRebased,
+	UpToDate,
+	Diverged,
+	DirtyTree,
+	FetchOnly,
+	DetachedHead,
+	NoUpstream,
+	Bare,
+}
+
+impl UpdateOutcome {
+	pub fn describe(&self) -> &'static str {
+		match self {
+			UpdateOutcome::FastForwarded => "fast-forwarded",
+			UpdateOutcome::Rebased => "rebased",
+			UpdateOutcome::AutostashRebased => "rebased with local changes stashed",
+			UpdateOutcome::UpToDate => "already up to date",
+			UpdateOutcome::Diverged => "diverged from upstream, not updated",
+			UpdateOutcome::DirtyTree => "local changes block the update",
+			UpdateOutcome::FetchOnly => "fetched only",
+			UpdateOutcome::DetachedHead => "detached HEAD, not updated",
+			UpdateOutcome::NoUpstream => "branch has no upstream, not updated",
+			UpdateOutcome::Bare => "bare repository, not updated",
+		}
+	}
+
+	/// Whether the checked out branch moved.
+	pub fn is_updated(&self) -> bool {
+		matches!(self, UpdateOutcome::FastForwarded | UpdateOutcome::Rebased | UpdateOutcome::AutostashRebased)
+	}
+
+	/// Whether the checkout could not be brought up to date although an update was requested.
+	pub fn is_stale(&self) -> bool {
+		matches!(
+			self,
+			UpdateOutcome::Diverged | UpdateOutcome::DirtyTree | UpdateOutcome::DetachedHead | UpdateOutcome::NoUpstream
+		)
+	}
+}
+
+fn has_local_changes(repo: &Repository) -> Result<bool, AppError> {
+	let mut options = StatusOptions::new();
+	options.include_untracked(false).include_ignored(false);
+	Ok(!repo.statuses(Some(&mut options))?.is_empty())
+}
+
+/// Why deleting the repository would lose work: uncommitted or untracked files, stashed changes, or local branches that
+/// are ahead of their upstream or have none.
+pub fn unsaved_work(repo: &Repository) -> Result<Option<String>, AppError> {
+	if !repo.is_bare() {
+		let mut options = StatusOptions::new();
+		options.include_untracked(true).include_ignored(false);
+		if !repo.statuses(Some(&mut options))?.is_empty() {
+			return Ok(Some("uncommitted changes".to_owned()));
+		}
+	}
+	if repo.find_reference("refs/stash").is_ok() {
+		return Ok(Some("stashed changes".to_owned()));
+	}
+	for maybe_branch in repo.branches(Some(BranchType::Local))? {
+		let (branch, _) = maybe_branch?;
+		let name = branch.name()?.unwrap_or_default().to_owned();
+		let Some(local_oid) = branch.get().target() else { continue };
+		match branch.upstream() {
+			Ok(upstream) => {
+				if let Some(upstream_oid) = upstream.get().target() {
+					let (ahead, _) = repo.graph_ahead_behind(local_oid, upstream_oid)?;
+					if ahead > 0 {
+						return Ok(Some(format!("branch {name} is {ahead} commit(s) ahead of its upstream")));
+					}
+				}
+			}
+			Err(e) if e.code() == ErrorCode::NotFound => return Ok(Some(format!("branch {name} has no upstream"))),
+			Err(e) => return Err(AppError::GitError(e)),
+		}
+	}
+	Ok(None)
+}
+
+/// Brings the checked out branch up to date with its upstream using the given strategy.
+/// `use_git_cli` is set for partial and sparse clones which libgit2 can not check out.
+pub fn update_head(repo: &mut Repository, path: &Path, strategy: UpdateStrategy, use_git_cli: bool) -> Result<UpdateOutcome, AppError> {
+	if strategy == UpdateStrategy::FetchOnly {
+		return Ok(UpdateOutcome::FetchOnly);
+	}
+	if repo.is_bare() {
+		return Ok(UpdateOutcome::Bare);
+	}
+	let head = match repo.head() {
+		Ok(head) if head.is_branch() => head,
+		Ok(_) => return Ok(UpdateOutcome::DetachedHead),
+		// unborn branch without any commits
+		Err(e) if e.code() == ErrorCode::UnbornBranch => return Ok(UpdateOutcome::NoUpstream),
+		Err(e) => return Err(AppError::GitError(e)),
+	};
+	let (local_oid, upstream_oid) = match (head.target(), Branch::wrap(head).upstream().ok().and_then(|u| u.get().target())) {
+		(Some(local), Some(upstream)) => (local, upstream),
+		_ => return Ok(UpdateOutcome::NoUpstream),
+	};
+	let (ahead, behind) = repo.graph_ahead_behind(local_oid, upstream_oid)?;
+	if behind == 0 {
+		return Ok(UpdateOutcome::UpToDate);
+	}
+	let dirty = has_local_changes(repo)?;
+	match strategy {
+		UpdateStrategy::Ff if ahead > 0 => Ok(UpdateOutcome::Diverged),
+		UpdateStrategy::Rebase if dirty => Ok(UpdateOutcome::DirtyTree),
+		UpdateStrategy::Ff if use_git_cli => match cli::fast_forward(path) {
+			Ok(()) => Ok(UpdateOutcome::FastForwarded),
+			// git refuses to fast-forward when local changes are in the way
+			Err(_) if dirty => Ok(UpdateOutcome::DirtyTree),
+			Err(e) => Err(e),
+		},
+		UpdateStrategy::Ff => fast_forward(repo, upstream_oid),
+		// the rebase was aborted because of conflicts
+		_ if use_git_cli => Ok(match cli::rebase(path, strategy == UpdateStrategy::AutostashRebase) {
+			Ok(()) if dirty => UpdateOutcome::AutostashRebased,
+			Ok(()) => UpdateOutcome::Rebased,
+			Err(_) => UpdateOutcome::Diverged,
+		}),
+		_ if dirty => autostash(repo, rebase).map(|outcome| {
+			if outcome == UpdateOutcome::Rebased {
+				UpdateOutcome::AutostashRebased
+			} else {
+				outcome
+			}
+		}),
+		_ => rebase(repo),
+	}
+}
+
+pub(super) fn fast_forward(repo: &Repository, upstream_oid: git2::Oid) -> Result<UpdateOutcome, AppError> {
+	// the default (safe) checkout refuses to overwrite local changes
+	match repo.checkout_tree(&repo.find_object(upstream_oid, None)?, None) {
+		Ok(()) => {
+			repo.head()?.set_target(upstream_oid, "fw fast-forward")?;
+			Ok(UpdateOutcome::FastForwarded)
+		}
+		Err(e) if e.code() == ErrorCode::Conflict => Ok(UpdateOutcome::DirtyTree),
+		Err(e) => Err(AppError::GitError(e)),
+	}
+}
+
+/// Rebases the checked out branch onto its upstream. A rebase with conflicts is aborted and reported as diverged.
+fn rebase(repo: &mut Repository) -> Result<UpdateOutcome, AppError> {
+	if rebase_onto_upstream(repo)?.is_empty() {
+		Ok(UpdateOutcome::Rebased)
+	} else {
+		Ok(UpdateOutcome::Diverged)
+	}
+}
+
+/// Rebases the checked out branch onto its upstream and returns the conflicting paths. A rebase with conflicts or
+/// errors is aborted so the repository is left as it was.
+pub(super) fn rebase_onto_upstream(repo: &mut Repository) -> Result<Vec<String>, AppError> {
+	let upstream = Branch::wrap(repo.head()?).upstream()?;
+	let upstream_commit = repo.reference_to_annotated_commit(upstream.get())?;
+	let signature = repo.signature()?;
+	let mut options = RebaseOptions::new();
+	let mut rebase = repo.rebase(None, Some(&upstream_commit), None, Some(&mut options))?;
+	match apply_rebase(repo, &mut rebase, &signature) {
+		Ok(conflicts) if conflicts.is_empty() => Ok(conflicts),
+		Ok(conflicts) => {
+			rebase.abort()?;
+			Ok(conflicts)
+		}
+		Err(e) => {
+			// the error of the rebase is more useful than one of the abort
+			let _ = rebase.abort();
+			Err(e)
+		}
+	}
+}
+
+/// Applies all operations of the rebase and finishes it unless one of them conflicts.
+fn apply_rebase(repo: &Repository, rebase: &mut Rebase<'_>, signature: &Signature<'_>) -> Result<Vec<String>, AppError> {
+	while let Some(operation) = rebase.next() {
+		operation?;
+		let index = repo.index()?;
+		if index.has_conflicts() {
+			return Ok(
+				index
+					.conflicts()?
+					.filter_map(Result::ok)
+					.filter_map(|conflict| conflict.our.or(conflict.their))
+					.map(|entry| String::from_utf8_lossy(&entry.path).to_string())
+					.collect(),
+			);
+		}
+		match rebase.commit(None, signature, None) {
+			// the commit is already part of upstream
+			Err(e) if e.code() == ErrorCode::Applied => (),
+			result => {
+				result?;
+			}
+		}
+	}
+	rebase.finish(Some(signature))?;
+	Ok(vec![])
+}
+
+fn autostash<F>(repo: &mut Repository, operation: F) -> Result<UpdateOutcome, AppError>
+where
+	F: FnOnce(&mut Repository) -> Result<UpdateOutcome, AppError>,
+{
+	let signature = repo.signature()?;
+	repo.stash_save(&signature, "fw autostash", None)?;
+	let outcome = operation(repo);
+	repo.stash_pop(0, None).map_err(|e| {
+		AppError::RuntimeError(format!(
+			"Could not re-apply the local changes after updating ({}). They are kept in the stash.",
+			e.message()
+		))
+	})?;
+	outcome
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_stale_outcomes() {
+		assert!(UpdateOutcome::DetachedHead.is_stale());
+		assert!(UpdateOutcome::Diverged.is_stale());
+		assert!(!UpdateOutcome::UpToDate.is_stale());
+		assert!(!UpdateOutcome::FetchOnly.is_stale());
+		// nothing to update in a bare repository
+		assert!(!UpdateOutcome::Bare.is_stale());
+	}
+	#[test]
+	fn test_unsaved_work() {
+		let path = std::env::temp_dir().join(format!("fw-test-unsaved-{}", std::process::id()));
+		let mut repo = Repository::init(&path).unwrap();
+		assert_eq!(unsaved_work(&repo).unwrap(), None);
+		std::fs::write(path.join("notes"), "todo").unwrap();
+		assert_eq!(unsaved_work(&repo).unwrap(), Some("uncommitted changes".to_owned()));
+		commit_all(&repo, "notes", &[]);
+		assert!(unsaved_work(&repo).unwrap().unwrap().ends_with("has no upstream"));
+		std::fs::write(path.join("notes"), "done").unwrap();
+		let signature = git2::Signature::now("fw", "fw@example.com").unwrap();
+		repo.stash_save(&signature, "wip", None).unwrap();
+		assert_eq!(unsaved_work(&repo).unwrap(), Some("stashed changes".to_owned()));
+		std::fs::remove_dir_all(&path).unwrap();
+	}
+
+	fn commit_all(repo: &Repository, message: &str, parents: &[&git2::Commit<'_>]) -> git2::Oid {
+		let mut index = repo.index().unwrap();
+		index.add_all(["*"], git2::IndexAddOption::DEFAULT, None).unwrap();
+		index.update_all(["*"], None).unwrap();
+		index.write().unwrap();
+		let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+		let signature = git2::Signature::now("fw", "fw@example.com").unwrap();
+		repo.commit(Some("HEAD"), &signature, &signature, message, &tree, parents).unwrap()
+	}
+
+	#[test]
+	fn test_failed_rebase_is_aborted() {
+		let path = std::env::temp_dir().join(format!("fw-test-rebase-{}", std::process::id()));
+		let mut repo = Repository::init(&path).unwrap();
+		repo.config().unwrap().set_str("user.name", "fw").unwrap();
+		repo.config().unwrap().set_str("user.email", "fw@example.com").unwrap();
+		std::fs::write(path.join("base"), "base").unwrap();
+		let local = {
+			let base = repo.find_commit(commit_all(&repo, "base", &[])).unwrap();
+			repo.branch("upstream", &base, false).unwrap();
+			// a local commit adds a file that the next one removes again
+			std::fs::write(path.join("notes"), "todo").unwrap();
+			let added = repo.find_commit(commit_all(&repo, "add notes", &[&base])).unwrap();
+			std::fs::remove_file(path.join("notes")).unwrap();
+			let local = commit_all(&repo, "remove notes", &[&added]);
+			repo.set_head("refs/heads/upstream").unwrap();
+			repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force())).unwrap();
+			std::fs::write(path.join("upstream"), "upstream").unwrap();
+			commit_all(&repo, "upstream", &[&base]);
+			local
+		};
+		let branch_name = repo.config().unwrap().get_string("init.defaultBranch").unwrap_or_else(|_| "master".to_owned());
+		repo.set_head(&format!("refs/heads/{branch_name}")).unwrap();
+		repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force())).unwrap();
+		repo
+			.find_branch(&branch_name, BranchType::Local)
+			.unwrap()
+			.set_upstream(Some("upstream"))
+			.unwrap();
+		// the untracked file can't be overwritten when the first commit is applied
+		std::fs::write(path.join("notes"), "untracked").unwrap();
+
+		assert!(rebase_onto_upstream(&mut repo).is_err());
+		assert_eq!(repo.state(), git2::RepositoryState::Clean);
+		assert_eq!(repo.head().unwrap().shorthand(), Some(branch_name.as_str()));
+		assert_eq!(repo.head().unwrap().target(), Some(local));
+		assert_eq!(std::fs::read_to_string(path.join("notes")).unwrap(), "untracked");
+		std::fs::remove_dir_all(&path).unwrap();
+	}
+}
@@ -0,0 +1,173 @@
+use crate::config::project::{Project, Worktree};
+use crate::config::{Config, expand_path};
+use crate::errors::AppError;
+
+use git2::{BranchType, Repository, WorktreeAddOptions, WorktreePruneOptions};
+
+use std::path::{Path, PathBuf};
+
+/// Where the worktree is checked out: next to the project as `<project path>-<branch>` unless configured otherwise.
+pub fn worktree_path(project_path: &Path, worktree: &Worktree) -> PathBuf {
+	match worktree.path {
+		Some(ref path) => {
+			let path = expand_path(PathBuf::from(path));
+			if path.is_absolute() { path } else { project_path.join(path) }
+		}
+		None => {
+			let mut name = project_path.as_os_str().to_owned();
+			name.push(format!("-{}", worktree_name(worktree)));
+			PathBuf::from(name)
+		}
+	}
+}
+
+/// Name of the worktree in .git/worktrees. Branch names may contain slashes, worktree names may not.
+fn worktree_name(worktree: &Worktree) -> String {
+	worktree.branch.replace('/', "-")
+}
+
+/// Whether the branch is checked out in one of the linked worktrees of the repository.
+pub fn checked_out_in_worktree(local: &Repository, branch_name: &str) -> Result<bool, AppError> {
+	let reference = format!("refs/heads/{branch_name}");
+	for name in local.worktrees()?.iter().flatten() {
+		let head = local
+			.find_worktree(name)
+			.and_then(|worktree| Repository::open_from_worktree(&worktree))
+			.and_then(|repo| repo.head().map(|head| head.name().map(ToOwned::to_owned)));
+		if let Ok(Some(head)) = head
+			&& head == reference
+		{
+			return Ok(true);
+		}
+	}
+	Ok(false)
+}
+
+/// Whether `path` is checked out as one of the linked worktrees of `local`.
+fn is_worktree_of(local: &Repository, path: &Path) -> bool {
+	Repository::open(path).is_ok_and(|repo| repo.is_worktree() && same_dir(repo.commondir(), local.commondir()))
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+	match (a.canonicalize(), b.canonicalize()) {
+		(Ok(a), Ok(b)) => a == b,
+		_ => a == b,
+	}
+}
+
+/// Fails if the worktree would be checked out where another project lives or something else already exists.
+fn check_worktree_path(config: &Config, local: &Repository, project: &Project, worktree: &Worktree, path: &Path) -> Result<(), AppError> {
+	if let Some(other) = config
+		.projects
+		.values()
+		.find(|other| other.name != project.name && same_dir(&config.actual_path_to_project(other), path))
+	{
+		return Err(AppError::UserError(format!(
+			"Can not create worktree for {} at {}: it is the path of project {}. Set a path for the worktree.",
+			worktree.branch,
+			path.display(),
+			other.name
+		)));
+	}
+	if path.exists() && !is_worktree_of(local, path) {
+		return Err(AppError::UserError(format!(
+			"Can not create worktree for {} at {}: it already exists and is no worktree of {}",
+			worktree.branch,
+			path.display(),
+			project.name
+		)));
+	}
+	Ok(())
+}
+
+/// Creates the declared worktrees that do not exist yet. The local branch is created from origin if it is missing.
+pub fn create_missing_worktrees(config: &Config, local: &Repository, project: &Project, project_path: &Path) -> Result<(), AppError> {
+	for worktree in project.worktrees.clone().unwrap_or_default() {
+		let path = worktree_path(project_path, &worktree);
+		check_worktree_path(config, local, project, &worktree, &path)?;
+		if path.exists() {
+			continue;
+		}
+		let name = worktree_name(&worktree);
+		// the checkout was deleted by hand. drop the stale administrative files so the worktree can be added again
+		if let Ok(stale) = local.find_worktree(&name) {
+			stale.prune(Some(WorktreePruneOptions::new().valid(true).working_tree(true)))?;
+		}
+		if local.find_branch(&worktree.branch, BranchType::Local).is_err() {
+			let upstream_name = format!("origin/{}", worktree.branch);
+			let upstream = local
+				.find_branch(&upstream_name, BranchType::Remote)
+				.map_err(|_| AppError::UserError(format!("Can not create worktree for {}: {upstream_name} does not exist", worktree.branch)))?;
+			let commit = upstream.get().peel_to_commit()?;
+			local.branch(&worktree.branch, &commit, false)?.set_upstream(Some(&upstream_name))?;
+		}
+		let branch = local.find_branch(&worktree.branch, BranchType::Local)?;
+		let mut options = WorktreeAddOptions::new();
+		options.reference(Some(branch.get()));
+		local.worktree(&name, &path, Some(&options))?;
+	}
+	Ok(())
+}
+
+/// Paths of the declared worktrees that are checked out.
+pub fn existing_worktree_paths(local: &Repository, project: &Project, project_path: &Path) -> Vec<PathBuf> {
+	project
+		.worktrees
+		.clone()
+		.unwrap_or_default()
+		.iter()
+		.map(|worktree| worktree_path(project_path, worktree))
+		.filter(|path| is_worktree_of(local, path))
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_default_worktree_path() {
+		let worktree = Worktree {
+			branch: "release/1.0".to_owned(),
+			path: None,
+		};
+		assert_eq!(worktree_path(Path::new("/ws/fw"), &worktree), PathBuf::from("/ws/fw-release-1.0"));
+	}
+	#[test]
+	fn test_worktree_path_must_not_be_taken() {
+		let workspace = std::env::temp_dir().join(format!("fw-test-worktrees-{}", std::process::id()));
+		let local = Repository::init(workspace.join("fw")).unwrap();
+		let project = |name: &str| Project {
+			name: name.to_owned(),
+			override_path: Some(workspace.join(name).to_string_lossy().to_string()),
+			..Project::example()
+		};
+		let config = Config {
+			projects: [project("fw"), project("fw-release")].into_iter().map(|p| (p.name.clone(), p)).collect(),
+			settings: Default::default(),
+			sources: Default::default(),
+		};
+		let worktree = |branch: &str| Worktree {
+			branch: branch.to_owned(),
+			path: None,
+		};
+		let fw = project("fw");
+		let path_of = |branch: &str| worktree_path(&workspace.join("fw"), &worktree(branch));
+
+		// the default path is the one of another project
+		assert!(check_worktree_path(&config, &local, &fw, &worktree("release"), &path_of("release")).is_err());
+		assert!(check_worktree_path(&config, &local, &fw, &worktree("next"), &path_of("next")).is_ok());
+		// something else is in the way
+		std::fs::create_dir_all(path_of("next")).unwrap();
+		assert!(check_worktree_path(&config, &local, &fw, &worktree("next"), &path_of("next")).is_err());
+		std::fs::remove_dir_all(&workspace).unwrap();
+	}
+	#[test]
+	fn test_relative_worktree_path() {
+		let worktree = Worktree {
+			branch: "release".to_owned(),
+			path: Some("../fw-stable".to_owned()),
+		};
+		assert_eq!(worktree_path(Path::new("/ws/fw"), &worktree), PathBuf::from("/ws/fw/../fw-stable"));
+	}
+}
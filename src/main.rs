@@ -1,4 +1,6 @@
+use crate::config::project::UpdateStrategy;
 use crate::errors::AppError;
+use crate::ws::github::RepositorySource;
 use std::collections::BTreeSet;
 
 fn main() {
@@ -14,7 +16,7 @@ fn _main() -> i32 {
 
 	let config = config::read_config();
 	if config.is_err() {
-		eprintln!("Could not read v2.0 config: {config:?}. If you are running the setup right now this is expected.");
+		eprintln!("Could not read config: {config:?}. If you are running the setup right now this is expected.");
 	};
 
 	let subcommand_name = matches.subcommand_name().expect("subcommand required by clap.rs").to_owned();
@@ -23,18 +25,43 @@ fn _main() -> i32 {
 	let result: Result<(), AppError> = match subcommand_name.as_ref() {
 		"sync" => {
 			let worker = subcommand_matches.get_one::<i32>("parallelism").expect("enforced by clap.rs").to_owned();
-
-			sync::synchronize(
-				config,
-				subcommand_matches.get_flag("only-new"),
-				!subcommand_matches.get_flag("no-fast-forward-merge"),
-				&subcommand_matches
-					.get_many::<String>("tag")
-					.unwrap_or_default()
-					.map(ToOwned::to_owned)
-					.collect(),
-				worker,
-			)
+			let orphan_action = if subcommand_matches.get_flag("import-untracked") {
+				sync::OrphanAction::Import
+			} else if subcommand_matches.get_flag("delete-untracked") {
+				sync::OrphanAction::Delete
+			} else {
+				sync::OrphanAction::Report
+			};
+			let strategy_override = if subcommand_matches.get_flag("no-fast-forward-merge") {
+				Ok(Some(UpdateStrategy::FetchOnly))
+			} else {
+				subcommand_matches
+					.get_one::<String>("update-strategy")
+					.map(|s| s.parse::<UpdateStrategy>())
+					.transpose()
+			};
+			subcommand_matches
+				.get_one::<String>("report")
+				.map(|r| r.parse::<sync::report::ReportFormat>())
+				.transpose()
+				.and_then(|report_format| strategy_override.map(|strategy_override| (report_format, strategy_override)))
+				.and_then(|(report_format, strategy_override)| {
+					sync::synchronize(
+						config,
+						subcommand_matches.get_flag("only-new"),
+						strategy_override,
+						&subcommand_matches
+							.get_many::<String>("tag")
+							.unwrap_or_default()
+							.map(ToOwned::to_owned)
+							.collect(),
+						worker,
+						report_format,
+						subcommand_matches.get_flag("prune"),
+						orphan_action,
+						subcommand_matches.get_flag("yes"),
+					)
+				})
 		}
 		"add-remote" => {
 			let name: &str = subcommand_matches.get_one::<String>("NAME").expect("argument required by clap.rs");
@@ -47,6 +74,21 @@ fn _main() -> i32 {
 			let remote_name: &str = subcommand_matches.get_one::<String>("REMOTE_NAME").expect("argument required by clap.rs");
 			project::remove_remote(config, name, remote_name.to_string())
 		}
+		"new" => project::new_project(
+			config,
+			subcommand_matches.get_one::<String>("NAME").expect("argument required by clap.rs"),
+			subcommand_matches.get_one::<String>("organization").map(String::as_str),
+			subcommand_matches.get_flag("private"),
+			subcommand_matches.get_one::<String>("description").map(String::as_str),
+			subcommand_matches
+				.get_many::<String>("tag")
+				.map(|v| v.into_iter().map(ToOwned::to_owned).collect()),
+		),
+		"fork" => project::fork(
+			config,
+			subcommand_matches.get_one::<String>("NAME").expect("argument required by clap.rs"),
+			subcommand_matches.get_one::<String>("organization").map(String::as_str),
+		),
 		"add" => {
 			let name: Option<String> = subcommand_matches.get_one::<String>("NAME").map(ToOwned::to_owned);
 			let url: &str = subcommand_matches.get_one::<String>("URL").expect("argument required by clap.rs");
@@ -57,7 +99,8 @@ fn _main() -> i32 {
 				.get_many::<String>("tag")
 				.map(|v| v.into_iter().map(ToOwned::to_owned).collect());
 			let trusted = subcommand_matches.get_flag("trusted");
-			project::add_entry(config, name, url, after_workon, after_clone, override_path, tags, trusted)
+			let branch: Option<String> = subcommand_matches.get_one::<String>("branch").map(ToOwned::to_owned);
+			project::add_entry(config, name, url, after_workon, after_clone, override_path, tags, trusted, branch)
 		}
 		"remove" => project::remove_project(
 			config,
@@ -75,18 +118,73 @@ fn _main() -> i32 {
 			let after_workon: Option<String> = subcommand_matches.get_one::<String>("after-workon").map(ToOwned::to_owned);
 			let after_clone: Option<String> = subcommand_matches.get_one::<String>("after-clone").map(ToOwned::to_owned);
 			let override_path: Option<String> = subcommand_matches.get_one::<String>("override-path").map(ToOwned::to_owned);
-			project::update_entry(config, name, git, after_workon, after_clone, override_path)
+			let branch: Option<String> = subcommand_matches.get_one::<String>("branch").map(ToOwned::to_owned);
+			project::update_entry(config, name, git, after_workon, after_clone, override_path, branch)
 		}
 		"setup" => setup::setup(subcommand_matches.get_one::<String>("WORKSPACE_DIR").expect("argument required by clap.rs")),
 		"import" => setup::import(
 			config,
 			subcommand_matches.get_one::<String>("PROJECT_DIR").expect("argument required by clap.rs"),
 		),
-		"org-import" => setup::org_import(
-			config,
-			subcommand_matches.get_one::<String>("ORG_NAME").expect("argument required by clap.rs"),
-			subcommand_matches.get_flag("include-archived"),
-		),
+		"org-import" => {
+			let filter = ws::RepositoryFilter {
+				topics: subcommand_matches
+					.get_many::<String>("topic")
+					.unwrap_or_default()
+					.map(ToOwned::to_owned)
+					.collect(),
+				languages: subcommand_matches
+					.get_many::<String>("language")
+					.unwrap_or_default()
+					.map(ToOwned::to_owned)
+					.collect(),
+				visibility: subcommand_matches.get_one::<String>("visibility").map(ToOwned::to_owned),
+			};
+			let sources = if let Some(login) = subcommand_matches.get_one::<String>("user") {
+				let mut sources = vec![RepositorySource::User {
+					login: login.to_owned(),
+					include_forks: subcommand_matches.get_flag("forks"),
+				}];
+				if subcommand_matches.get_flag("starred") {
+					sources.push(RepositorySource::Starred(login.to_owned()));
+				}
+				Ok(sources)
+			} else if let Some(team) = subcommand_matches.get_one::<String>("team") {
+				RepositorySource::team(team).map(|team| vec![team])
+			} else {
+				Ok(vec![RepositorySource::Organization(
+					subcommand_matches
+						.get_one::<String>("ORG_NAME")
+						.expect("argument required by clap.rs")
+						.to_owned(),
+				)])
+			};
+			subcommand_matches
+				.get_one::<String>("provider")
+				.expect("has default value")
+				.parse::<ws::Provider>()
+				.and_then(|provider| sources.map(|sources| (provider, sources)))
+				.and_then(|(provider, sources)| {
+					if subcommand_matches.get_flag("reconcile") {
+						let options = setup::ReconcileOptions {
+							update_urls: subcommand_matches.get_flag("update-urls"),
+							tag_archived: subcommand_matches.get_flag("tag-archived"),
+							remove_deleted: subcommand_matches.get_flag("remove-deleted"),
+							https: subcommand_matches.get_flag("https"),
+						};
+						setup::org_reconcile(config, provider, &sources, options)
+					} else {
+						setup::org_import(
+							config,
+							provider,
+							&sources,
+							subcommand_matches.get_flag("include-archived"),
+							&filter,
+							subcommand_matches.get_flag("https"),
+						)
+					}
+				})
+		}
 		"gen-workon" => workon::r#gen(
 			subcommand_matches.get_one::<String>("PROJECT_NAME").expect("argument required by clap.rs"),
 			config,
@@ -115,6 +213,33 @@ fn _main() -> i32 {
 				.collect(),
 			&subcommand_matches.get_one::<String>("parallel").map(ToOwned::to_owned),
 		),
+		"status" => status::status(
+			config,
+			&subcommand_matches
+				.get_many::<String>("tag")
+				.unwrap_or_default()
+				.map(ToOwned::to_owned)
+				.collect(),
+			subcommand_matches.get_flag("json"),
+			&subcommand_matches.get_one::<i32>("parallel").map(ToString::to_string),
+		),
+		"doctor" => doctor::doctor(config),
+		"prs" => subcommand_matches
+			.get_one::<String>("filter")
+			.expect("has default value")
+			.parse::<prs::PullRequestFilter>()
+			.and_then(|filter| {
+				prs::prs(
+					config,
+					&subcommand_matches
+						.get_many::<String>("tag")
+						.unwrap_or_default()
+						.map(ToOwned::to_owned)
+						.collect(),
+					filter,
+					subcommand_matches.get_flag("json"),
+				)
+			}),
 		"print-zsh-setup" => crate::shell::print_zsh_setup(subcommand_matches.get_flag("with-fzf"), subcommand_matches.get_flag("with-skim")),
 		"print-bash-setup" => crate::shell::print_bash_setup(subcommand_matches.get_flag("with-fzf"), subcommand_matches.get_flag("with-skim")),
 		"print-fish-setup" => crate::shell::print_fish_setup(subcommand_matches.get_flag("with-fzf"), subcommand_matches.get_flag("with-skim")),
@@ -126,6 +251,14 @@ fn _main() -> i32 {
 				.to_owned();
 			execute_tag_subcommand(config, &subsubcommand_name, &subsubcommand_matches)
 		}
+		"config" => {
+			let subsubcommand_name: String = subcommand_matches.subcommand_name().expect("subcommand matches enforced by clap.rs").to_owned();
+			let subsubcommand_matches: clap::ArgMatches = subcommand_matches
+				.subcommand_matches(&subsubcommand_name)
+				.expect("subcommand matches enforced by clap.rs")
+				.to_owned();
+			execute_config_subcommand(config, &subsubcommand_name, &subsubcommand_matches)
+		}
 		"ls" => project::ls(
 			config,
 			&subcommand_matches
@@ -198,7 +331,8 @@ fn execute_tag_subcommand(maybe_config: Result<config::Config, AppError>, tag_co
 			let after_clone: Option<String> = tag_matches.get_one::<String>("after-clone").map(ToOwned::to_owned);
 			let tag_workspace: Option<String> = tag_matches.get_one::<String>("workspace").map(ToOwned::to_owned);
 			let priority: Option<u8> = tag_matches.get_one::<u8>("priority").map(ToOwned::to_owned);
-			tag::create_tag(maybe_config, tag_name, after_workon, after_clone, priority, tag_workspace)
+			let branch: Option<String> = tag_matches.get_one::<String>("branch").map(ToOwned::to_owned);
+			tag::create_tag(maybe_config, tag_name, after_workon, after_clone, priority, tag_workspace, branch)
 		}
 		"autotag" => tag::autotag(
 			maybe_config,
@@ -213,16 +347,31 @@ fn execute_tag_subcommand(maybe_config: Result<config::Config, AppError>, tag_co
 	}
 }
 
+fn execute_config_subcommand(
+	maybe_config: Result<config::Config, AppError>,
+	config_command_name: &str,
+	config_matches: &clap::ArgMatches,
+) -> Result<(), AppError> {
+	match config_command_name {
+		"migrate" => config::migrate::migrate(config_matches.get_flag("dry-run")),
+		"sync" => config::sync::sync(maybe_config),
+		_ => Result::Err(AppError::InternalError("Command not implemented")),
+	}
+}
+
 mod app;
 mod config;
+mod doctor;
 mod errors;
 mod git;
 mod intellij;
 mod project;
 mod projectile;
+mod prs;
 mod setup;
 mod shell;
 mod spawn;
+mod status;
 mod sync;
 mod tag;
 mod util;
@@ -1,12 +1,15 @@
 use crate::config;
 use crate::config::Config;
-use crate::config::{project::Project, project::Remote};
+use crate::config::{project::Project, project::Remote, project::UpdateStrategy};
 use crate::errors::AppError;
-use crate::git::repo_name_from_url;
+use crate::git::{self, repo_name_from_url};
+use crate::ws;
 use std::collections::BTreeSet;
 use std::{fs, path};
 use yansi::Paint;
 
+const UPSTREAM_REMOTE: &str = "upstream";
+
 pub fn add_entry(
 	maybe_config: Result<Config, AppError>,
 	maybe_name: Option<String>,
@@ -16,6 +19,7 @@ pub fn add_entry(
 	override_path: Option<String>,
 	tags: Option<BTreeSet<String>>,
 	trusted: bool,
+	branch: Option<String>,
 ) -> Result<(), AppError> {
 	let name = maybe_name
 		.ok_or_else(|| AppError::UserError(format!("No project name specified for {url}")))
@@ -45,6 +49,16 @@ pub fn add_entry(
 			bare: None,
 			additional_remotes: None,
 			trusted,
+			retries: None,
+			retry_backoff_ms: None,
+			branch,
+			clone_depth: None,
+			clone_filter: None,
+			sparse_checkout: None,
+			submodules: None,
+			worktrees: None,
+			update_strategy: None,
+			remote_id: None,
 			project_config_path: "default".to_string(),
 		})?;
 		Ok(())
@@ -57,6 +71,8 @@ pub fn remove_project(maybe_config: Result<Config, AppError>, project_name: &str
 	if !config.projects.contains_key(project_name) {
 		Err(AppError::UserError(format!("Project key {project_name} does not exist in config")))
 	} else if let Some(project) = config.projects.get(project_name).cloned() {
+		// first because it is refused for projects of a shared config dir
+		config::delete_project_config(&project)?;
 		if purge_directory {
 			let path = config.actual_path_to_project(&project);
 
@@ -64,7 +80,7 @@ pub fn remove_project(maybe_config: Result<Config, AppError>, project_name: &str
 				fs::remove_dir_all(&path)?;
 			}
 		}
-		config::delete_project_config(&project)
+		Ok(())
 	} else {
 		Err(AppError::UserError(format!("Unknown project {project_name}")))
 	}
@@ -89,6 +105,111 @@ pub fn add_remote(maybe_config: Result<Config, AppError>, name: &str, remote_nam
 	Ok(())
 }
 
+/// Creates a new repository on github, registers it as project and initializes it in the workspace.
+pub fn new_project(
+	maybe_config: Result<Config, AppError>,
+	name: &str,
+	organization: Option<&str>,
+	private: bool,
+	description: Option<&str>,
+	tags: Option<BTreeSet<String>>,
+) -> Result<(), AppError> {
+	let config: Config = maybe_config?;
+	if config.projects.contains_key(name) {
+		return Err(AppError::UserError(format!(
+			"Project key {name} already exists, not gonna overwrite it for you"
+		)));
+	}
+	let project_tags: Option<BTreeSet<String>> = match (tags, config.settings.default_tags.clone()) {
+		(Some(tags), Some(default_tags)) => Some(tags.union(&default_tags).cloned().collect()),
+		(tags, default_tags) => tags.or(default_tags),
+	};
+	let mut project = Project {
+		name: name.to_owned(),
+		git: String::new(),
+		after_clone: config.settings.default_after_clone.clone(),
+		after_workon: config.settings.default_after_workon.clone(),
+		override_path: None,
+		tags: project_tags,
+		bare: None,
+		additional_remotes: None,
+		trusted: false,
+		retries: None,
+		retry_backoff_ms: None,
+		branch: None,
+		clone_depth: None,
+		clone_filter: None,
+		sparse_checkout: None,
+		submodules: None,
+		worktrees: None,
+		update_strategy: None,
+		remote_id: None,
+		project_config_path: "default".to_string(),
+	};
+	let path = config.actual_path_to_project(&project);
+	if path.exists() {
+		return Err(AppError::UserError(format!(
+			"Path {} already exists, not gonna overwrite it for you",
+			path.display()
+		)));
+	}
+
+	let repository = ws::create_repository(&config.settings, organization, name, private, description)?;
+	project.git = repository.git;
+	project.remote_id = repository.id;
+	config::write_project(&project)?;
+	println!("Created {} in {}", project.git, path.display());
+	git::init_project(&config, &project, &path)
+}
+
+/// The project pointing to the fork, with the original as upstream remote. It gets the id of the fork so that
+/// reconciling the import of the original doesn't take the fork for a renamed repository.
+pub(crate) fn forked_project(project: Project, fork: &ws::RemoteRepository) -> Project {
+	let mut additional_remotes = project.additional_remotes.clone().unwrap_or_default();
+	let https = project.git.starts_with("http");
+	additional_remotes.push(Remote {
+		name: UPSTREAM_REMOTE.to_owned(),
+		git: project.git.clone(),
+	});
+	Project {
+		git: fork.clone_url(https).to_owned(),
+		additional_remotes: Some(additional_remotes),
+		remote_id: fork.id.clone(),
+		..project
+	}
+}
+
+/// Forks the project on github, points the project to the fork and keeps the original as the upstream remote.
+pub fn fork(maybe_config: Result<Config, AppError>, name: &str, organization: Option<&str>) -> Result<(), AppError> {
+	let config: Config = maybe_config?;
+	let project_config: Project = config
+		.projects
+		.get(name)
+		.cloned()
+		.ok_or_else(|| AppError::UserError(format!("Project key {name} does not exists. Can not fork.")))?;
+	if project_config.additional_remotes.iter().flatten().any(|r| r.name == UPSTREAM_REMOTE) {
+		return Err(AppError::UserError(format!(
+			"Remote {UPSTREAM_REMOTE} for project {name} does already exist. Is it already a fork?"
+		)));
+	}
+	let (owner, repository) = ws::github::repository_path(&project_config.git)
+		.ok_or_else(|| AppError::UserError(format!("Can not determine the github repository of {}", project_config.git)))?;
+
+	let fork = ws::fork_repository(&config.settings, &owner, &repository, organization)?;
+	let forked_project = forked_project(project_config, &fork);
+	config::write_project(&forked_project)?;
+	println!("Forked {}/{} to {}", owner, repository, forked_project.git);
+
+	let path = config.actual_path_to_project(&forked_project);
+	if path.exists() {
+		// github creates forks asynchronously, the next sync will catch up if the fork is not ready yet
+		if let Err(e) = git::update_project_remotes(&config, &forked_project, &path, UpdateStrategy::FetchOnly, false) {
+			eprintln!("Could not update the remotes of {name} yet, run fw sync later: {e}");
+		}
+	}
+	Ok(())
+}
+
 pub fn remove_remote(maybe_config: Result<Config, AppError>, name: &str, remote_name: String) -> Result<(), AppError> {
 	let config: Config = maybe_config?;
 	if !config.projects.contains_key(name) {
@@ -110,6 +231,7 @@ pub fn update_entry(
 	after_workon: Option<String>,
 	after_clone: Option<String>,
 	override_path: Option<String>,
+	branch: Option<String>,
 ) -> Result<(), AppError> {
 	let config: Config = maybe_config?;
 	if name.starts_with("http") || name.starts_with("git@") {
@@ -130,6 +252,16 @@ pub fn update_entry(
 			bare: old_project_config.bare,
 			trusted: old_project_config.trusted,
 			additional_remotes: old_project_config.additional_remotes,
+			retries: old_project_config.retries,
+			retry_backoff_ms: old_project_config.retry_backoff_ms,
+			branch: branch.or(old_project_config.branch),
+			clone_depth: old_project_config.clone_depth,
+			clone_filter: old_project_config.clone_filter,
+			sparse_checkout: old_project_config.sparse_checkout,
+			submodules: old_project_config.submodules,
+			worktrees: old_project_config.worktrees,
+			update_strategy: old_project_config.update_strategy,
+			remote_id: old_project_config.remote_id,
 			project_config_path: old_project_config.project_config_path,
 		})?;
 		Ok(())
@@ -177,6 +309,7 @@ pub fn inspect(name: &str, maybe_config: Result<Config, AppError>, json: bool) -
 	println!("{}", Paint::new(project.name.to_owned()).bold().underline());
 	println!("{:<20}: {}", "Path", path);
 	println!("{:<20}: {}", "config path", project.project_config_path);
+	println!("{:<20}: {}", "Source", config.sources.describe_project(name));
 	let tags = project
 		.tags
 		.clone()
@@ -197,6 +330,9 @@ pub fn inspect(name: &str, maybe_config: Result<Config, AppError>, json: bool) -
 	println!("{:<20}: {}", "Additional remotes", additional_remotes);
 	let git = project.git.clone();
 	println!("{:<20}: {}", "Git", git);
+	if let Some(branch) = config.resolve_branch(project) {
+		println!("{:<20}: {}", "Branch", branch);
+	}
 	Ok(())
 }
 
@@ -214,6 +350,6 @@ pub(crate) fn move_project(maybe_config: Result<Config, AppError>, name: &str, d
 
 	path = fs::canonicalize(path)?;
 	let project_path = path.to_str().ok_or(AppError::InternalError("project path is not valid unicode"))?.to_owned();
-	update_entry(Ok(config), name, None, None, None, Some(project_path))?;
+	update_entry(Ok(config), name, None, None, None, Some(project_path), None)?;
 	Ok(())
 }
@@ -0,0 +1,144 @@
+use crate::config::{Config, project::Project};
+use crate::errors::AppError;
+use crate::ws::{self, github::PullRequest};
+
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+use yansi::Paint;
+
+/// Github limits search queries to 256 characters, `is:pr is:open ` is added by the search.
+const MAX_QUALIFIERS_LENGTH: usize = 256 - "is:pr is:open ".len();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PullRequestFilter {
+	All,
+	/// Authored by the owner of the token
+	Mine,
+	/// Review requested from the owner of the token (or one of their teams)
+	ReviewRequested,
+}
+
+impl PullRequestFilter {
+	fn qualifier(&self) -> Option<&'static str> {
+		match self {
+			PullRequestFilter::All => None,
+			PullRequestFilter::Mine => Some("author:@me"),
+			PullRequestFilter::ReviewRequested => Some("review-requested:@me"),
+		}
+	}
+}
+
+impl FromStr for PullRequestFilter {
+	type Err = AppError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"all" => Ok(PullRequestFilter::All),
+			"mine" => Ok(PullRequestFilter::Mine),
+			"review-requested" => Ok(PullRequestFilter::ReviewRequested),
+			other => Err(AppError::UserError(format!("Unknown filter {other}. Use all, mine or review-requested."))),
+		}
+	}
+}
+
+#[derive(Serialize, Debug)]
+pub struct ProjectPullRequests {
+	pub project: String,
+	pub repository: String,
+	pub pull_requests: Vec<PullRequest>,
+}
+
+/// The search qualifiers for the repositories, split into as few searches as the maximum length of a search allows.
+fn searches(filter: PullRequestFilter, repositories: &BTreeSet<String>) -> Vec<String> {
+	let mut searches: Vec<Vec<String>> = Vec::new();
+	let mut current: Vec<String> = Vec::new();
+	for repository in repositories {
+		if current.is_empty() {
+			current.extend(filter.qualifier().map(ToOwned::to_owned));
+		}
+		let qualifier = format!("repo:{repository}");
+		let length = current.iter().map(|q| q.len() + 1).sum::<usize>() + qualifier.len();
+		if length > MAX_QUALIFIERS_LENGTH && current.len() > filter.qualifier().iter().count() {
+			searches.push(current);
+			current = filter.qualifier().into_iter().map(ToOwned::to_owned).collect();
+		}
+		current.push(qualifier);
+	}
+	if !current.is_empty() {
+		searches.push(current);
+	}
+	searches.into_iter().map(|qualifiers| qualifiers.join(" ")).collect()
+}
+
+/// Project names by `owner/name` of the projects hosted on github. Lowercase because github ignores case.
+fn github_repositories<'a>(config: &Config, projects: impl Iterator<Item = &'a Project>) -> BTreeMap<String, String> {
+	let hosts = ws::github_hosts(&config.settings);
+	projects
+		.filter(|p| ws::github::repository_host(&p.git).is_some_and(|host| hosts.contains(host)))
+		.filter_map(|p| ws::github::repository_path(&p.git).map(|(owner, name)| (format!("{owner}/{name}").to_lowercase(), p.name.clone())))
+		.collect()
+}
+
+fn group_by_project(repositories: &BTreeMap<String, String>, pull_requests: Vec<PullRequest>) -> Vec<ProjectPullRequests> {
+	let mut grouped: BTreeMap<String, ProjectPullRequests> = BTreeMap::new();
+	for pull_request in pull_requests {
+		if let Some(project) = repositories.get(&pull_request.repository.name_with_owner.to_lowercase()) {
+			grouped
+				.entry(project.clone())
+				.or_insert_with(|| ProjectPullRequests {
+					project: project.clone(),
+					repository: pull_request.repository.name_with_owner.clone(),
+					pull_requests: vec![],
+				})
+				.pull_requests
+				.push(pull_request);
+		}
+	}
+	grouped.into_values().collect()
+}
+
+pub fn prs(maybe_config: Result<Config, AppError>, tags: &BTreeSet<String>, filter: PullRequestFilter, json: bool) -> Result<(), AppError> {
+	let config = maybe_config?;
+	let projects = config
+		.projects
+		.values()
+		.filter(|p| tags.is_empty() || p.tags.clone().unwrap_or_default().intersection(tags).count() > 0);
+	let repositories = github_repositories(&config, projects);
+	let names: BTreeSet<String> = repositories.keys().cloned().collect();
+	let pull_requests = ws::search_pull_requests(&config.settings, &searches(filter, &names))?;
+	let grouped = group_by_project(&repositories, pull_requests);
+
+	if json {
+		println!("{}", serde_json::to_string(&grouped)?);
+	} else {
+		for project in grouped {
+			println!("{} ({})", Paint::new(&project.project).bold(), project.repository);
+			for pull_request in project.pull_requests {
+				let author = pull_request.author.map(|a| a.login).unwrap_or_else(|| "ghost".to_owned());
+				let draft = if pull_request.is_draft { " [draft]" } else { "" };
+				println!("  #{} {} ({}){} {}", pull_request.number, pull_request.title, author, draft, pull_request.url);
+			}
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_searches_are_split_at_max_length() {
+		let repositories: BTreeSet<String> = (0..20).map(|i| format!("brocode/repository-{i:02}")).collect();
+		let searches = searches(PullRequestFilter::Mine, &repositories);
+
+		assert!(searches.len() > 1);
+		assert!(searches.iter().all(|s| s.starts_with("author:@me repo:") && s.len() <= MAX_QUALIFIERS_LENGTH));
+		assert_eq!(searches.iter().map(|s| s.matches("repo:").count()).sum::<usize>(), 20);
+	}
+	#[test]
+	fn test_no_search_without_repositories() {
+		assert!(searches(PullRequestFilter::All, &BTreeSet::new()).is_empty());
+	}
+}
@@ -1,13 +1,21 @@
-use crate::config::{self, Config, project::Project, settings::Settings};
+use crate::config::{
+	self, Config,
+	project::Project,
+	settings::{ImportTags, Settings},
+};
 use crate::errors::AppError;
-use crate::ws::github;
+use crate::ws::github::RepositorySource;
+use crate::ws::{self, Provider, RemoteRepository, RepositoryFilter};
 use git2::Repository;
-use std::collections::BTreeMap;
-use std::env;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
 use std::iter::Iterator;
 use std::path::{Path, PathBuf};
 
+mod reconcile;
+
+pub use reconcile::{ReconcileOptions, org_reconcile};
+
 pub fn setup(workspace_dir: &str) -> Result<(), AppError> {
 	let path = PathBuf::from(workspace_dir);
 	let maybe_path = if path.exists() {
@@ -56,42 +64,74 @@ fn determine_projects(path: PathBuf) -> Result<BTreeMap<String, Project>, AppErr
 	Ok(projects)
 }
 
-pub fn org_import(maybe_config: Result<Config, AppError>, org_name: &str, include_archived: bool) -> Result<(), AppError> {
+/// The default tags plus the tags mapped from the topics, language and archived flag of the repository.
+fn imported_tags(default_tags: Option<&BTreeSet<String>>, import_tags: Option<&ImportTags>, repository: &RemoteRepository) -> Option<BTreeSet<String>> {
+	let mapped = import_tags
+		.map(|i| i.tags_for(&repository.topics, repository.language.as_deref(), repository.archived))
+		.unwrap_or_default();
+	if mapped.is_empty() {
+		return default_tags.cloned();
+	}
+	Some(default_tags.cloned().unwrap_or_default().union(&mapped).cloned().collect())
+}
+
+pub fn org_import(
+	maybe_config: Result<Config, AppError>,
+	provider: Provider,
+	sources: &[RepositorySource],
+	include_archived: bool,
+	filter: &RepositoryFilter,
+	https: bool,
+) -> Result<(), AppError> {
 	let current_config = maybe_config?;
-	let token = env::var_os("FW_GITHUB_TOKEN")
-		.map(|s| s.to_string_lossy().to_string())
-		.or_else(|| current_config.settings.github_token.clone())
-		.ok_or_else(|| {
-			AppError::UserError(format!(
-				"Can't call GitHub API for org {org_name} because no github oauth token (settings.github_token) specified in the configuration."
-			))
-		})?;
-	let mut api = github::github_api(&token)?;
-	let org_repository_names: Vec<String> = api.list_repositories(org_name, include_archived)?;
+	let mut org_repositories: Vec<(RemoteRepository, String)> = Vec::new();
+	for source in sources {
+		for repository in ws::list_repositories(&current_config.settings, provider, source, include_archived)? {
+			if filter.matches(&repository) {
+				org_repositories.push((repository, source.config_path()));
+			}
+		}
+	}
 	let after_clone = current_config.settings.default_after_clone.clone();
 	let after_workon = current_config.settings.default_after_workon.clone();
 	let tags = current_config.settings.default_tags.clone();
+	let import_tags = current_config.settings.import_tags.clone();
 	let mut current_projects = current_config.projects;
 
-	for name in org_repository_names {
+	for (repository, config_path) in org_repositories {
 		let p = Project {
-			name: name.clone(),
-			git: format!("git@github.com:{org_name}/{name}.git"),
+			name: repository.name.clone(),
+			git: repository.clone_url(https).to_owned(),
 			after_clone: after_clone.clone(),
 			after_workon: after_workon.clone(),
 			override_path: None,
-			tags: tags.clone(),
+			tags: imported_tags(tags.as_ref(), import_tags.as_ref(), &repository),
 			additional_remotes: None,
 			bare: None,
 			trusted: false,
-			project_config_path: org_name.to_string(),
+			retries: None,
+			retry_backoff_ms: None,
+			branch: None,
+			clone_depth: None,
+			clone_filter: None,
+			sparse_checkout: None,
+			submodules: None,
+			worktrees: None,
+			update_strategy: None,
+			remote_id: repository.id.clone(),
+			project_config_path: config_path,
 		};
 
-		if current_projects.contains_key(&p.name) {
-			//     "Skipping new project from Github import because it already exists in the current fw config
-		} else {
-			config::write_project(&p)?;
-			current_projects.insert(p.name.clone(), p); // to ensure no duplicated name encountered during processing
+		match current_projects.get(&p.name) {
+			Some(existing) if existing.git != p.git => eprintln!(
+				"Skipping {} ({}) from the import because a different project with that name already exists",
+				p.name, p.git
+			),
+			Some(_) => (),
+			None => {
+				config::write_project(&p)?;
+				current_projects.insert(p.name.clone(), p); // to ensure no duplicated name encountered during processing
+			}
 		}
 	}
 	Ok(())
@@ -128,17 +168,42 @@ fn load_project(maybe_settings: Option<Settings>, path_to_repo: PathBuf, name: &
 		tags: maybe_settings.and_then(|s| s.default_tags),
 		bare: None,
 		trusted: false,
+		retries: None,
+		retry_backoff_ms: None,
+		branch: None,
+		clone_depth: None,
+		clone_filter: None,
+		sparse_checkout: None,
+		submodules: None,
+		worktrees: None,
+		update_strategy: None,
+		remote_id: None,
 		project_config_path: "default".to_string(),
 	})
 }
 
 fn write_new_config_with_projects(projects: BTreeMap<String, Project>, workspace_dir: &str) -> Result<(), AppError> {
 	let settings: config::settings::PersistedSettings = config::settings::PersistedSettings {
+		version: Some(config::migrate::CURRENT_SCHEMA_VERSION),
+		shared_config_dirs: None,
 		workspace: workspace_dir.to_owned(),
 		default_after_workon: None,
 		default_after_clone: None,
 		shell: None,
 		github_token: None,
+		github_token_command: None,
+		github_api_url: None,
+		github_ssh_host: None,
+		ssh_key_files: None,
+		gitlab_url: None,
+		gitlab_token: None,
+		gitea_url: None,
+		gitea_token: None,
+		bitbucket_url: None,
+		bitbucket_token: None,
+		import_tags: None,
+		retries: None,
+		retry_backoff_ms: None,
 	};
 	config::write_settings(&settings)?;
 	for p in projects.values() {
@@ -0,0 +1,260 @@
+use crate::config::{self, Config, project::Project};
+use crate::errors::AppError;
+use crate::ws::github::{RepositorySource, repository_full_path};
+use crate::ws::{self, Provider, RemoteRepository};
+
+use std::collections::{BTreeMap, BTreeSet};
+
+const ARCHIVED_TAG: &str = "archived";
+
+/// What to do about the differences found. Without any option the differences are only reported.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReconcileOptions {
+	pub update_urls: bool,
+	pub tag_archived: bool,
+	pub remove_deleted: bool,
+	/// Use the https instead of the ssh clone url when updating urls
+	pub https: bool,
+}
+
+#[derive(Debug)]
+enum Change<'a> {
+	New(&'a RemoteRepository),
+	Renamed(&'a Project, &'a RemoteRepository),
+	Archived(&'a Project),
+	Deleted(&'a Project),
+	/// Unchanged but imported before ids were recorded
+	MissingId(&'a Project, &'a RemoteRepository),
+}
+
+/// Whether the urls point to the same repository path. Scheme, user and host (e.g. an ssh alias) don't matter and
+/// prefixes like Bitbucket Server's `scm/` are ignored.
+fn same_path(git: &str, other: &str) -> bool {
+	match (repository_full_path(git), repository_full_path(other)) {
+		(Some(a), Some(b)) => {
+			let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+			long == short || long.ends_with(&format!("/{short}"))
+		}
+		_ => git == other,
+	}
+}
+
+/// Whether the project (or one of its additional remotes, e.g. the upstream of a fork) still points to the repository.
+fn points_to(project: &Project, repository: &RemoteRepository) -> bool {
+	std::iter::once(&project.git)
+		.chain(project.additional_remotes.iter().flatten().map(|r| &r.git))
+		.any(|git| same_path(git, &repository.git))
+}
+
+/// Compares the imported projects with the repositories listed by the provider. Projects are matched by the
+/// id of the repository if they have one and by name otherwise.
+fn compare<'a>(projects: &[&'a Project], repositories: &'a [RemoteRepository]) -> Vec<Change<'a>> {
+	let mut changes = Vec::new();
+	let mut matched: BTreeSet<usize> = BTreeSet::new();
+	for project in projects {
+		let found = repositories
+			.iter()
+			.position(|r| project.remote_id.is_some() && r.id == project.remote_id)
+			.or_else(|| repositories.iter().position(|r| r.name == project.name));
+		match found {
+			Some(index) => {
+				matched.insert(index);
+				let repository = &repositories[index];
+				if !points_to(project, repository) {
+					changes.push(Change::Renamed(project, repository));
+				} else if project.remote_id.is_none() && repository.id.is_some() {
+					changes.push(Change::MissingId(project, repository));
+				}
+				if repository.archived && !project.tags.clone().unwrap_or_default().contains(ARCHIVED_TAG) {
+					changes.push(Change::Archived(project));
+				}
+			}
+			None => changes.push(Change::Deleted(project)),
+		}
+	}
+	for (index, repository) in repositories.iter().enumerate() {
+		if !matched.contains(&index) {
+			changes.push(Change::New(repository));
+		}
+	}
+	changes
+}
+
+/// Reports (and on request fixes) the differences between the projects imported from the sources and the repositories
+/// that exist upstream today: new, renamed, archived and deleted repositories.
+pub fn org_reconcile(
+	maybe_config: Result<Config, AppError>,
+	provider: Provider,
+	sources: &[RepositorySource],
+	options: ReconcileOptions,
+) -> Result<(), AppError> {
+	let config = maybe_config?;
+	let config_paths: BTreeSet<String> = sources.iter().map(RepositorySource::config_path).collect();
+	for config_path in config_paths {
+		let mut repositories: Vec<RemoteRepository> = Vec::new();
+		for source in sources.iter().filter(|s| s.config_path() == config_path) {
+			repositories.extend(ws::list_repositories(&config.settings, provider, source, true)?);
+		}
+		let projects: Vec<&Project> = config.projects.values().filter(|p| p.project_config_path == config_path).collect();
+		// projects written above, so that tagging an archived project keeps its new url
+		let mut written: BTreeMap<String, Project> = BTreeMap::new();
+
+		for change in compare(&projects, &repositories) {
+			match change {
+				Change::New(repository) => println!(
+					"NEW: {} ({}){}",
+					repository.name,
+					repository.clone_url(options.https),
+					if repository.archived { " archived" } else { "" }
+				),
+				Change::Renamed(project, repository) => {
+					let git = repository.clone_url(options.https);
+					println!("RENAMED: {} -> {} ({})", project.name, repository.name, git);
+					if options.update_urls {
+						let updated = Project {
+							git: git.to_owned(),
+							remote_id: repository.id.clone(),
+							..project.clone()
+						};
+						config::write_project(&updated)?;
+						written.insert(project.name.clone(), updated);
+						println!("UPDATED: {} now uses {}", project.name, git);
+					}
+				}
+				Change::MissingId(project, repository) => {
+					// recorded so that a later rename can be detected
+					if options.update_urls {
+						let updated = Project {
+							remote_id: repository.id.clone(),
+							..project.clone()
+						};
+						config::write_project(&updated)?;
+						written.insert(project.name.clone(), updated);
+					}
+				}
+				Change::Archived(project) => {
+					println!("ARCHIVED: {}", project.name);
+					if options.tag_archived {
+						let project = written.remove(&project.name).unwrap_or_else(|| project.clone());
+						let mut tags = project.tags.clone().unwrap_or_default();
+						tags.insert(ARCHIVED_TAG.to_owned());
+						println!("TAGGED: {} with {}", project.name, ARCHIVED_TAG);
+						config::write_project(&Project { tags: Some(tags), ..project })?;
+					}
+				}
+				Change::Deleted(project) => {
+					println!("DELETED: {} no longer exists upstream", project.name);
+					if options.remove_deleted {
+						config::delete_project_config(project)?;
+						println!("REMOVED: {} from the fw config", project.name);
+					}
+				}
+			}
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn project(name: &str, remote_id: Option<&str>) -> Project {
+		Project {
+			name: name.to_owned(),
+			git: format!("git@github.com:brocode/{name}.git"),
+			remote_id: remote_id.map(ToOwned::to_owned),
+			tags: None,
+			..Project::example()
+		}
+	}
+
+	fn repository(id: &str, name: &str, archived: bool) -> RemoteRepository {
+		RemoteRepository {
+			id: Some(id.to_owned()),
+			name: name.to_owned(),
+			git: format!("git@github.com:brocode/{name}.git"),
+			archived,
+			..RemoteRepository::default()
+		}
+	}
+
+	fn summary(changes: Vec<Change>) -> Vec<String> {
+		changes
+			.into_iter()
+			.map(|change| match change {
+				Change::New(repository) => format!("new {}", repository.name),
+				Change::Renamed(project, repository) => format!("renamed {} {}", project.name, repository.name),
+				Change::Archived(project) => format!("archived {}", project.name),
+				Change::Deleted(project) => format!("deleted {}", project.name),
+				Change::MissingId(project, repository) => format!("missing-id {} {:?}", project.name, repository.id),
+			})
+			.collect()
+	}
+
+	#[test]
+	fn test_compare_detects_all_changes() {
+		let renamed = project("fw-old", Some("R_1"));
+		let archived = project("old", Some("R_2"));
+		let deleted = project("gone", Some("R_3"));
+		let repositories = vec![
+			repository("R_1", "fw", false),
+			repository("R_2", "old", true),
+			repository("R_4", "brand-new", false),
+		];
+		assert_eq!(
+			summary(compare(&[&renamed, &archived, &deleted], &repositories)),
+			vec!["renamed fw-old fw", "archived old", "deleted gone", "new brand-new"]
+		);
+	}
+	#[test]
+	fn test_compare_matches_by_name_without_id() {
+		let unchanged = project("fw", None);
+		let repositories = vec![repository("R_1", "fw", false)];
+		assert_eq!(summary(compare(&[&unchanged], &repositories)), vec!["missing-id fw Some(\"R_1\")"]);
+	}
+	#[test]
+	fn test_compare_accepts_https_url() {
+		let https = Project {
+			git: "https://github.com/brocode/fw.git".to_owned(),
+			..project("fw", Some("R_1"))
+		};
+		let repositories = vec![RemoteRepository {
+			https_git: Some("https://github.com/brocode/fw.git".to_owned()),
+			..repository("R_1", "fw", false)
+		}];
+		assert!(compare(&[&https], &repositories).is_empty());
+	}
+	#[test]
+	fn test_compare_ignores_scheme_and_host_alias() {
+		let aliased = Project {
+			git: "git@github-work:Brocode/fw".to_owned(),
+			..project("fw", Some("R_1"))
+		};
+		let https = Project {
+			git: "https://github.com/brocode/old.git".to_owned(),
+			..project("old", Some("R_2"))
+		};
+		let repositories = vec![repository("R_1", "fw", false), repository("R_2", "old", false)];
+		assert!(compare(&[&aliased, &https], &repositories).is_empty());
+	}
+	#[test]
+	fn test_fork_is_not_renamed() {
+		let fork = RemoteRepository {
+			git: "git@github.com:someone/fw.git".to_owned(),
+			..repository("R_9", "fw", false)
+		};
+		let forked = crate::project::forked_project(project("fw", Some("R_1")), &fork);
+		assert_eq!(forked.remote_id, Some("R_9".to_owned()));
+		let repositories = vec![repository("R_1", "fw", false)];
+		assert!(compare(&[&forked], &repositories).is_empty());
+	}
+	#[test]
+	fn test_same_path_ignores_bitbucket_server_prefix() {
+		assert!(same_path(
+			"ssh://git@bitbucket.example.com:7999/prj/fw.git",
+			"https://bitbucket.example.com/scm/prj/fw.git"
+		));
+		assert!(!same_path("git@github.com:brocode/fw.git", "git@github.com:someone/fw.git"));
+	}
+}
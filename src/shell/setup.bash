@@ -179,18 +179,24 @@ __fw_complete()
         local cmds=(
             'add-remote'
             'add'
+            'config'
+            'doctor'
             'foreach'
+            'fork'
             'help '
             'import'
             'inspect'
             'ls'
+            'new'
             'org-import'
             'print-path'
             'projectile'
+            'prs'
             'remove-remote'
             'remove'
             'reworkon'
             'setup'
+            'status'
             'sync'
             'tag'
             'update'
@@ -223,7 +229,7 @@ __fw_complete()
 
     _fw_add() {
         case "$cur" in
-            --*) __fw_comp "--after-clone --after-workon --override-path" ; return ;;
+            --*) __fw_comp "--after-clone --after-workon --branch --override-path" ; return ;;
         esac
     }
 
@@ -231,6 +237,24 @@ __fw_complete()
         __fw_comp "$(__fw_projects)"
     }
 
+    _fw_new () {
+        case "$prev" in
+            --tag|-t) __fw_comp "$(__fw_tags)" ; return ;;
+        esac
+
+        case "$cur" in
+            --*) __fw_comp "--organization --private --description --tag" ; return ;;
+        esac
+    }
+
+    _fw_fork () {
+        case "$cur" in
+            --*) __fw_comp "--organization" ; return ;;
+        esac
+
+        __fw_comp "$(__fw_projects)"
+    }
+
     _fw_foreach () {
         case "$prev" in
             --tag|-t) __fw_comp "$(__fw_tags)" ; return ;;
@@ -258,8 +282,13 @@ __fw_complete()
     }
 
     _fw_org_import () {
+        case "$prev" in
+            --provider) __fw_comp "github gitlab gitea forgejo bitbucket bitbucket-server" ; return ;;
+            --visibility) __fw_comp "public private internal" ; return ;;
+        esac
+
         case "$cur" in
-            --*) __fw_comp "--include-archived" ; return ;;
+            --*) __fw_comp "--include-archived --https --provider --user --forks --starred --team --topic --language --visibility --reconcile --update-urls --tag-archived --remove-deleted" ; return ;;
         esac
     }
 
@@ -282,9 +311,39 @@ __fw_complete()
     # _fw_reworkon() {
     # }
 
+    _fw_prs () {
+        case "$prev" in
+            --tag|-t) __fw_comp "$(__fw_tags)" ; return ;;
+            --filter) __fw_comp "all mine review-requested" ; return ;;
+        esac
+
+        case "$cur" in
+            --*) __fw_comp "--filter --json --tag" ; return ;;
+        esac
+    }
+
+    _fw_status () {
+        case "$prev" in
+            --tag|-t) __fw_comp "$(__fw_tags)" ; return ;;
+        esac
+
+        case "$cur" in
+            --*) __fw_comp "--json --tag" ; return ;;
+        esac
+    }
+
     _fw_sync () {
         case "$cur" in
-            --*) __fw_comp "--no-ff-merge --no-progress-bar --only-new --parallelism" ; return ;;
+            --*) __fw_comp "--no-ff-merge --no-progress-bar --only-new --parallelism --report --prune --import-untracked --delete-untracked --yes --update-strategy" ; return ;;
+        esac
+    }
+
+    _fw_config () {
+        local subcommands='help migrate sync '
+        local subcommand="$(__find_on_cmdline "$subcommands")"
+        case "$subcommand,$cur" in
+            ,*) __fw_comp "$subcommands" ;;
+            migrate,--*) __fw_comp "--dry-run" ;;
         esac
     }
 
@@ -302,7 +361,7 @@ __fw_complete()
 
     _fw_tag_add() {
         case "$cur" in
-            --*) __fw_comp "--after-clone --after-workon --workspace" ; return ;;
+            --*) __fw_comp "--after-clone --after-workon --branch --workspace" ; return ;;
         esac
         __fw_comp "$(__fw_tags)"
     }
@@ -350,7 +409,7 @@ __fw_complete()
         esac
 
         case "$cur" in
-            --*) __fw_comp "--after-clone --after-workon --git-url --override-path" ; return ;;
+            --*) __fw_comp "--after-clone --after-workon --branch --git-url --override-path" ; return ;;
         esac
 
         __fw_comp "$(__fw_projects)"
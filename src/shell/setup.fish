@@ -15,14 +15,20 @@ function __fw_subcommands
     'add-remote:Add remote to project' \
     'remove-remote:Removes remote from project' \
     'remove:Remove project from workspace' \
+    'config:Manage the fw config' \
+    'doctor:Check config and workspace for problems' \
     'foreach:Run script on each project' \
+    'fork:Fork project on github' \
+    'status:Show git status of all projects' \
     'projectile:Create projectile bookmarks' \
+    'prs:Show open github pull requests' \
     'ls:List projects' \
+    'new:Create repository on github and add it as project' \
     'inspect:Inspect project' \
     'update:Update project settings' \
     'tag:Manipulate tags' \
     'print-path:Print project path to stdout' \
-    'org-import:Import all repositories from a github org'
+    'org-import:Import all repositories from a github org, gitlab group etc.'
 
   for subcmd in $__fw_subcommands_in_zsh_format
     echo (string replace -r ':' '\t' $subcmd)
@@ -76,7 +82,7 @@ end
 
 function __fish_fw_needs_project_arg
   if __fish_fw_is_arg_n 2
-    __fish_fw_command_in add-remote remove-remote print-path inspect update remove
+    __fish_fw_command_in add-remote remove-remote fork print-path inspect update remove
   else if __fish_fw_is_arg_n 3 and __fish_fw_command_in tag
     __fish_fw_subcommand_in ls tag-project untag-project
   else
@@ -102,6 +108,7 @@ complete -ec fw
 
 complete -c fw -n '__fish_fw_needs_command' -f -xa '(__fw_subcommands)'
 complete -c fw -n '__fish_fw_command_in tag; and __fish_fw_is_arg_n 2' -f -xa '(__fw_tag_subcommands)'
+complete -c fw -n '__fish_fw_command_in config; and __fish_fw_is_arg_n 2' -f -xa 'migrate\t"Upgrade the config to the current schema version" sync\t"Sync the config dir with its git remote"'
 complete -c fw -n '__fish_fw_needs_project_arg' -f -xa '(__fw_projects)'
 complete -c fw -n '__fish_fw_needs_tag_arg' -f -xa '(__fw_tags)'
 
@@ -119,22 +126,70 @@ complete -c fw -n '__fish_fw_completion_for_command sync' -s n -l only-new \
   -d 'Only clones projects, skips all actions for projects already on your machine.'
 complete -c fw -n '__fish_fw_completion_for_command sync' -s p -l parallelism \
   -d 'Set the number of threads'
+complete -c fw -n '__fish_fw_completion_for_command sync' -l report -xa 'json junit' \
+  -d 'Print a per project report after syncing'
+complete -c fw -n '__fish_fw_completion_for_command sync' -l prune \
+  -d 'Delete remotes not in the fw config and report untracked repositories'
+complete -c fw -n '__fish_fw_completion_for_command sync' -l update-strategy -xa 'ff rebase autostash-rebase fetch-only' \
+  -d 'How the checked out branch is updated'
+complete -c fw -n '__fish_fw_completion_for_command sync' -l import-untracked
+complete -c fw -n '__fish_fw_completion_for_command sync' -l delete-untracked
+complete -c fw -n '__fish_fw_completion_for_command sync' -s y -l yes -d 'Delete untracked repositories without asking'
 
 complete -c fw -n '__fish_fw_completion_for_command org-import' -s a -l include-archived
+complete -c fw -n '__fish_fw_completion_for_command org-import' -l https -d 'Use https instead of ssh clone urls'
+complete -c fw -n '__fish_fw_completion_for_command org-import' -l provider -xa 'github gitlab gitea forgejo bitbucket bitbucket-server' \
+  -d 'The git hosting service to import from'
+complete -c fw -n '__fish_fw_completion_for_command org-import' -l user -x \
+  -d 'Import the repositories of a github user'
+complete -c fw -n '__fish_fw_completion_for_command org-import' -l forks
+complete -c fw -n '__fish_fw_completion_for_command org-import' -l starred
+complete -c fw -n '__fish_fw_completion_for_command org-import' -l team -x \
+  -d 'Import the repositories of a github team (ORG/TEAM)'
+complete -c fw -n '__fish_fw_completion_for_command org-import' -l topic -x
+complete -c fw -n '__fish_fw_completion_for_command org-import' -l language -x
+complete -c fw -n '__fish_fw_completion_for_command org-import' -l visibility -xa 'public private internal'
+complete -c fw -n '__fish_fw_completion_for_command org-import' -l reconcile \
+  -d 'Report new, renamed, archived and deleted repositories'
+complete -c fw -n '__fish_fw_completion_for_command org-import' -l update-urls
+complete -c fw -n '__fish_fw_completion_for_command org-import' -l tag-archived
+complete -c fw -n '__fish_fw_completion_for_command org-import' -l remove-deleted
+complete -c fw -n '__fish_fw_completion_for_command new' -l organization -x \
+  -d 'Create the repository in this organization instead of your account'
+complete -c fw -n '__fish_fw_completion_for_command new' -l private
+complete -c fw -n '__fish_fw_completion_for_command new' -l description -x
+complete -c fw -n '__fish_fw_completion_for_command new' -s t -l tag -xa '(__fw_tags)'
+complete -c fw -n '__fish_fw_completion_for_command fork' -l organization -x \
+  -d 'Fork into this organization instead of your account'
 
 complete -c fw -n '__fish_fw_completion_for_command foreach' -s p \
   -d 'Set the number of threads'
 complete -c fw -n '__fish_fw_completion_for_command foreach' -s t -l tag \
   -d 'Filter projects by tag. More than 1 is allowed.'
 
+complete -c fw -n '__fish_fw_completion_for_command status' -s p \
+  -d 'Set the number of threads'
+complete -c fw -n '__fish_fw_completion_for_command status' -s t -l tag \
+  -d 'Filter projects by tag. More than 1 is allowed.'
+complete -c fw -n '__fish_fw_completion_for_command status' -s j -l json
+
+complete -c fw -n '__fish_fw_completion_for_command prs' -s t -l tag \
+  -d 'Filter projects by tag. More than 1 is allowed.'
+complete -c fw -n '__fish_fw_completion_for_command prs' -l filter -xa 'all mine review-requested'
+complete -c fw -n '__fish_fw_completion_for_command prs' -s j -l json
+
 complete -c fw -n '__fish_fw_completion_for_command ls' -s t -l tag \
   -d 'Filter projects by tag. More than 1 is allowed.'
 
 complete -c fw -n '__fish_fw_completion_for_command update' -l after-clone
 complete -c fw -n '__fish_fw_completion_for_command update' -l after-workon
 complete -c fw -n '__fish_fw_completion_for_command update' -l git-url
+complete -c fw -n '__fish_fw_completion_for_command update' -l branch
 complete -c fw -n '__fish_fw_completion_for_command update' -l override-path
 
+complete -c fw -n '__fish_fw_completion_for_command_subcommand config migrate' -l dry-run \
+  -d 'Only print the migrations that would run'
+
 complete -c fw -n '__fish_fw_completion_for_command_subcommand tag add' -l after-clone
 complete -c fw -n '__fish_fw_completion_for_command_subcommand tag add' -l after-workon
 complete -c fw -n '__fish_fw_completion_for_command_subcommand tag add' -l git-url
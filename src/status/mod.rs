@@ -0,0 +1,196 @@
+use crate::config::{Config, project::Project};
+use crate::errors::AppError;
+use crate::spawn::init_threads;
+
+use git2::{Branch, Repository, StatusOptions};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::Path;
+use yansi::Paint;
+
+#[derive(Serialize, Debug, Default, Clone)]
+pub struct ProjectStatus {
+	pub name: String,
+	pub path: String,
+	pub missing: bool,
+	pub branch: Option<String>,
+	pub detached: bool,
+	pub upstream: Option<String>,
+	pub ahead: Option<usize>,
+	pub behind: Option<usize>,
+	pub dirty: usize,
+	pub untracked: usize,
+	pub stashes: usize,
+	pub error: Option<String>,
+}
+
+fn project_status(config: &Config, project: &Project) -> ProjectStatus {
+	let path = config.actual_path_to_project(project);
+	let mut status = ProjectStatus {
+		name: project.name.clone(),
+		path: path.to_string_lossy().to_string(),
+		..ProjectStatus::default()
+	};
+	if !path.exists() {
+		status.missing = true;
+	} else if let Err(e) = inspect_repository(&path, &mut status) {
+		status.error = Some(e.to_string());
+	}
+	status
+}
+
+fn inspect_repository(path: &Path, status: &mut ProjectStatus) -> Result<(), AppError> {
+	let mut repo = Repository::open(path)?;
+
+	if !repo.is_bare() {
+		let mut options = StatusOptions::new();
+		options.include_untracked(true).recurse_untracked_dirs(true).include_ignored(false);
+		for entry in repo.statuses(Some(&mut options))?.iter() {
+			if entry.status().is_wt_new() {
+				status.untracked += 1;
+			} else if !entry.status().is_ignored() {
+				status.dirty += 1;
+			}
+		}
+	}
+
+	match repo.head() {
+		Ok(head) if head.is_branch() => {
+			status.branch = head.shorthand().map(ToOwned::to_owned);
+			let local_oid = head.target();
+			if let Ok(upstream) = Branch::wrap(head).upstream() {
+				status.upstream = upstream.name()?.map(ToOwned::to_owned);
+				if let (Some(local), Some(remote)) = (local_oid, upstream.get().target()) {
+					let (ahead, behind) = repo.graph_ahead_behind(local, remote)?;
+					status.ahead = Some(ahead);
+					status.behind = Some(behind);
+				}
+			}
+		}
+		Ok(_) => status.detached = true,
+		// unborn branch (freshly initialized repository without commits)
+		Err(_) => {
+			status.branch = repo
+				.find_reference("HEAD")
+				.ok()
+				.and_then(|r| r.symbolic_target().map(|t| t.trim_start_matches("refs/heads/").to_owned()))
+		}
+	}
+
+	if !repo.is_bare() {
+		let mut stashes = 0;
+		repo.stash_foreach(|_, _, _| {
+			stashes += 1;
+			true
+		})?;
+		status.stashes = stashes;
+	}
+	Ok(())
+}
+
+fn format_branch(status: &ProjectStatus) -> String {
+	if status.missing {
+		"missing".to_owned()
+	} else if status.error.is_some() {
+		"error".to_owned()
+	} else if status.detached {
+		"(detached)".to_owned()
+	} else {
+		status.branch.clone().unwrap_or_default()
+	}
+}
+
+fn format_ahead_behind(status: &ProjectStatus) -> String {
+	match (status.ahead, status.behind) {
+		(Some(ahead), Some(behind)) => format!("+{ahead}/-{behind}"),
+		_ if status.branch.is_some() => "no upstream".to_owned(),
+		_ => "".to_owned(),
+	}
+}
+
+fn print_table(statuses: &[ProjectStatus]) {
+	let name_width = statuses.iter().map(|s| s.name.len()).max().unwrap_or_default().max("PROJECT".len());
+	let branch_width = statuses.iter().map(|s| format_branch(s).len()).max().unwrap_or_default().max("BRANCH".len());
+	println!(
+		"{}",
+		Paint::new(format!(
+			"{:<name_width$}  {:<branch_width$}  {:<12}  {:>5}  {:>9}  {:>6}",
+			"PROJECT", "BRANCH", "AHEAD/BEHIND", "DIRTY", "UNTRACKED", "STASH"
+		))
+		.bold()
+	);
+	for status in statuses {
+		let branch = format!("{:<branch_width$}", format_branch(status));
+		let branch = if status.missing || status.error.is_some() {
+			branch.red().to_string()
+		} else if status.detached {
+			branch.yellow().to_string()
+		} else {
+			branch
+		};
+		println!(
+			"{:<name_width$}  {}  {:<12}  {:>5}  {:>9}  {:>6}",
+			status.name,
+			branch,
+			format_ahead_behind(status),
+			status.dirty,
+			status.untracked,
+			status.stashes
+		);
+		if let Some(ref error) = status.error {
+			println!("{:<name_width$}  {}", "", error.red());
+		}
+	}
+}
+
+pub fn status(maybe_config: Result<Config, AppError>, tags: &BTreeSet<String>, json: bool, parallel_raw: &Option<String>) -> Result<(), AppError> {
+	let config = maybe_config?;
+	init_threads(parallel_raw)?;
+
+	let projects: Vec<&Project> = config.projects.values().collect();
+	let statuses: Vec<ProjectStatus> = projects
+		.par_iter()
+		.filter(|p| tags.is_empty() || p.tags.clone().unwrap_or_default().intersection(tags).count() > 0)
+		.map(|p| project_status(&config, p))
+		.collect();
+
+	if json {
+		println!("{}", serde_json::to_string(&statuses)?);
+	} else {
+		print_table(&statuses);
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_format_branch_missing() {
+		let status = ProjectStatus {
+			missing: true,
+			..ProjectStatus::default()
+		};
+		assert_eq!(format_branch(&status), "missing".to_owned());
+	}
+	#[test]
+	fn test_format_ahead_behind() {
+		let status = ProjectStatus {
+			branch: Some("master".to_owned()),
+			ahead: Some(2),
+			behind: Some(1),
+			..ProjectStatus::default()
+		};
+		assert_eq!(format_ahead_behind(&status), "+2/-1".to_owned());
+	}
+	#[test]
+	fn test_format_ahead_behind_without_upstream() {
+		let status = ProjectStatus {
+			branch: Some("master".to_owned()),
+			..ProjectStatus::default()
+		};
+		assert_eq!(format_ahead_behind(&status), "no upstream".to_owned());
+	}
+}
@@ -1,13 +1,18 @@
 use crate::config;
 use crate::config::metadata_from_repository::MetadataFromRepository;
-use crate::config::{Config, project::Project};
+use crate::config::{
+	Config,
+	project::{Project, UpdateStrategy},
+};
 use crate::errors::AppError;
 use std::collections::BTreeSet;
-use std::fs::read_to_string;
-use std::path::Path;
-use std::time::Duration;
+use std::fs::{self, read_to_string};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use crate::git::{clone_project, update_project_remotes};
+use crate::git::{ProjectUpdate, clone_project, ssh_agent_running, unsaved_work, update_project_remotes};
+use crate::setup;
+use git2::Repository;
 
 use crossbeam::queue::SegQueue;
 
@@ -18,20 +23,38 @@ use std::borrow::ToOwned;
 use std::sync::Arc;
 use std::thread;
 
-#[cfg(unix)]
-use std::os::unix::fs::FileTypeExt;
+pub mod report;
 
-fn sync_project(config: &Config, project: &Project, only_new: bool, ff_merge: bool) -> Result<(), AppError> {
+use report::{ProjectSyncReport, ReportFormat, SyncAction};
+
+fn sync_project(
+	config: &Config,
+	project: &Project,
+	only_new: bool,
+	strategy_override: Option<UpdateStrategy>,
+	prune: bool,
+) -> Result<(SyncAction, Option<ProjectUpdate>), AppError> {
 	let path = config.actual_path_to_project(project);
 	let exists = path.exists();
 	let result = if exists {
 		if only_new {
-			Ok(())
+			Ok((SyncAction::Skipped, None))
 		} else {
-			update_project_remotes(project, &path, ff_merge).and_then(|_| synchronize_metadata_if_trusted(project, &path))
+			let strategy = strategy_override.or(project.update_strategy).unwrap_or_default();
+			update_project_remotes(config, project, &path, strategy, prune).and_then(|update| {
+				synchronize_metadata_if_trusted(project, &path)?;
+				let action = if update.outcome.is_updated() {
+					SyncAction::FastForwarded
+				} else {
+					SyncAction::Fetched
+				};
+				Ok((action, Some(update)))
+			})
 		}
 	} else {
-		clone_project(config, project, &path).and_then(|_| synchronize_metadata_if_trusted(project, &path))
+		clone_project(config, project, &path)
+			.and_then(|_| synchronize_metadata_if_trusted(project, &path))
+			.map(|_| (SyncAction::Cloned, None))
 	};
 	result.map_err(|e| AppError::RuntimeError(format!("Failed to sync {}: {}", project.name, e)))
 }
@@ -58,10 +81,20 @@ pub fn synchronize_metadata_if_trusted(project: &Project, path: &Path) -> Result
 	}
 }
 
-pub fn synchronize(maybe_config: Result<Config, AppError>, only_new: bool, ff_merge: bool, tags: &BTreeSet<String>, worker: i32) -> Result<(), AppError> {
+pub fn synchronize(
+	maybe_config: Result<Config, AppError>,
+	only_new: bool,
+	strategy_override: Option<UpdateStrategy>,
+	tags: &BTreeSet<String>,
+	worker: i32,
+	report_format: Option<ReportFormat>,
+	prune: bool,
+	orphan_action: OrphanAction,
+	yes: bool,
+) -> Result<(), AppError> {
 	eprintln!("Synchronizing everything");
 	if !ssh_agent_running() {
-		eprintln!("SSH Agent not running. Process may hang.")
+		eprintln!("SSH Agent not running. Only configured ssh_key_files, tokens and credential helpers will be used.")
 	}
 	let config = Arc::new(maybe_config?);
 
@@ -83,6 +116,7 @@ pub fn synchronize(maybe_config: Result<Config, AppError>, only_new: bool, ff_me
 	m.set_draw_target(ProgressDrawTarget::stderr());
 
 	let job_results: Arc<SegQueue<Result<(), AppError>>> = Arc::new(SegQueue::new());
+	let project_reports: Arc<SegQueue<ProjectSyncReport>> = Arc::new(SegQueue::new());
 	let progress_bars = (1..=worker).map(|i| {
 		let pb = m.add(ProgressBar::new(projects_count));
 		pb.set_style(spinner_style.clone());
@@ -97,18 +131,22 @@ pub fn synchronize(maybe_config: Result<Config, AppError>, only_new: bool, ff_me
 		let job_q = Arc::clone(&q);
 		let job_config = Arc::clone(&config);
 		let job_result_queue = Arc::clone(&job_results);
+		let job_report_queue = Arc::clone(&project_reports);
 		thread_handles.push(thread::spawn(move || {
 			let mut job_result: Result<(), AppError> = Result::Ok(());
 			loop {
 				if let Some(project) = job_q.pop() {
 					pb.set_message(project.name.to_string());
-					let sync_result = sync_project(&job_config, &project, only_new, ff_merge);
+					let started = Instant::now();
+					let sync_result = sync_project(&job_config, &project, only_new, strategy_override, prune);
+					job_report_queue.push(ProjectSyncReport::new(&project.name, &sync_result, started.elapsed()));
 					let msg = match sync_result {
+						Ok((_, Some(ref update))) => format!("DONE: {} ({})", project.name, update.outcome.describe()),
 						Ok(_) => format!("DONE: {}", project.name),
 						Err(ref e) => format!("FAILED: {} - {}", project.name, e),
 					};
 					pb.println(&msg);
-					job_result = job_result.and(sync_result);
+					job_result = job_result.and(sync_result.map(|_| ()));
 				} else {
 					pb.finish_and_clear();
 					break;
@@ -129,22 +167,149 @@ pub fn synchronize(maybe_config: Result<Config, AppError>, only_new: bool, ff_me
 
 	m.clear().unwrap();
 
+	let mut reports: Vec<ProjectSyncReport> = Vec::new();
+	while let Some(report) = project_reports.pop() {
+		reports.push(report);
+	}
+	reports.sort_by(|a, b| a.project.cmp(&b.project));
+	print_stale_summary(&reports);
+
+	if let Some(format) = report_format {
+		println!("{}", report::render(format, &reports)?);
+	}
+
+	// after the report so that an error here doesn't hide it
+	if prune {
+		synchronize_result = synchronize_result.and(handle_orphaned_repositories(&config, orphan_action, yes));
+	}
 	synchronize_result
 }
 
-fn ssh_agent_running() -> bool {
-	match std::env::var("SSH_AUTH_SOCK") {
-		Ok(auth_socket) => is_socket(&auth_socket),
-		Err(_) => false,
+/// Lists the checkouts (and worktrees) that could not be updated so that nobody works on a stale branch unknowingly.
+fn print_stale_summary(reports: &[ProjectSyncReport]) {
+	let stale = stale_checkouts(reports);
+	if !stale.is_empty() {
+		eprintln!("{} checkout(s) were not updated:", stale.len());
+		for (checkout, reason) in stale {
+			eprintln!("STALE: {checkout} - {reason}");
+		}
+	}
+}
+
+fn stale_checkouts(reports: &[ProjectSyncReport]) -> Vec<(String, &'static str)> {
+	let mut stale = Vec::new();
+	for report in reports {
+		if let Some(outcome) = report.outcome.filter(|o| o.is_stale()) {
+			stale.push((report.project.clone(), outcome.describe()));
+		}
+		for worktree in report.worktrees.iter().filter(|w| w.outcome.is_stale()) {
+			stale.push((format!("{} (worktree {})", report.project, worktree.path), worktree.outcome.describe()));
+		}
+	}
+	stale
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrphanAction {
+	Report,
+	Import,
+	Delete,
+}
+
+/// Git repositories directly inside a workspace directory that are not tracked by any project.
+fn find_orphaned_repositories(config: &Config) -> Result<Vec<PathBuf>, AppError> {
+	let workspaces = config.workspaces();
+	let project_paths: BTreeSet<PathBuf> = config.projects.values().map(|p| config.actual_path_to_project(p)).collect();
+	let mut orphans: Vec<PathBuf> = Vec::new();
+	for workspace in workspaces.iter().filter(|w| w.is_dir()) {
+		for entry in fs::read_dir(workspace)? {
+			let path = entry?.path();
+			if path.is_dir() && !project_paths.contains(&path) && !workspaces.contains(&path) && Repository::open(&path).is_ok_and(|r| !r.is_worktree()) {
+				orphans.push(path);
+			}
+		}
+	}
+	orphans.sort();
+	Ok(orphans)
+}
+
+fn handle_orphaned_repositories(config: &Config, orphan_action: OrphanAction, yes: bool) -> Result<(), AppError> {
+	let orphans = find_orphaned_repositories(config)?;
+	if orphan_action == OrphanAction::Delete {
+		return delete_orphaned_repositories(orphans, yes);
+	}
+	for orphan in orphans {
+		let path = orphan.to_string_lossy().to_string();
+		match orphan_action {
+			OrphanAction::Import => match setup::import(Ok(config.clone()), &path) {
+				Ok(()) => eprintln!("IMPORTED: {path}"),
+				Err(e) => eprintln!("FAILED: could not import {path} - {e}"),
+			},
+			_ => eprintln!("UNTRACKED: {path} is not part of your fw config"),
+		}
+	}
+	Ok(())
+}
+
+/// Deletes the orphans that have no unsaved work after listing them and asking for confirmation (unless `yes`).
+fn delete_orphaned_repositories(orphans: Vec<PathBuf>, yes: bool) -> Result<(), AppError> {
+	let mut deletable: Vec<PathBuf> = Vec::new();
+	for orphan in orphans {
+		match Repository::open(&orphan).map_err(AppError::GitError).and_then(|repo| unsaved_work(&repo)) {
+			Ok(None) => {
+				eprintln!("WOULD DELETE: {}", orphan.display());
+				deletable.push(orphan);
+			}
+			Ok(Some(reason)) => eprintln!("SKIPPED: {}: {}", orphan.display(), reason),
+			Err(e) => eprintln!("SKIPPED: {}: {}", orphan.display(), e),
+		}
+	}
+	if deletable.is_empty() {
+		return Ok(());
 	}
+	if !yes && !confirm(&format!("Delete {} untracked repositories?", deletable.len()))? {
+		eprintln!("Nothing deleted. Pass --yes to delete without asking.");
+		return Ok(());
+	}
+	for orphan in deletable {
+		fs::remove_dir_all(&orphan)?;
+		eprintln!("DELETED: {}", orphan.display());
+	}
+	Ok(())
 }
 
-#[cfg(unix)]
-fn is_socket(path: &str) -> bool {
-	std::fs::metadata(path).map(|m| m.file_type().is_socket()).unwrap_or(false)
+fn confirm(question: &str) -> Result<bool, AppError> {
+	eprint!("{question} [y/N] ");
+	let mut answer = String::new();
+	std::io::stdin().read_line(&mut answer)?;
+	Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
 }
 
-#[cfg(not(unix))]
-fn is_socket(_: &str) -> bool {
-	false
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::git::{UpdateOutcome, WorktreeUpdate};
+
+	#[test]
+	fn test_stale_checkouts_include_worktrees() {
+		let update = ProjectUpdate {
+			outcome: UpdateOutcome::FastForwarded,
+			worktrees: vec![WorktreeUpdate {
+				path: "/ws/fw-release".to_owned(),
+				outcome: UpdateOutcome::Diverged,
+			}],
+		};
+		let bare = ProjectUpdate {
+			outcome: UpdateOutcome::Bare,
+			worktrees: vec![],
+		};
+		let reports = vec![
+			ProjectSyncReport::new("fw", &Ok((SyncAction::FastForwarded, Some(update))), Duration::ZERO),
+			ProjectSyncReport::new("mirror", &Ok((SyncAction::Fetched, Some(bare))), Duration::ZERO),
+		];
+		assert_eq!(
+			stale_checkouts(&reports),
+			vec![("fw (worktree /ws/fw-release)".to_owned(), UpdateOutcome::Diverged.describe())]
+		);
+	}
 }
@@ -0,0 +1,176 @@
+use crate::errors::AppError;
+use crate::git::{ProjectUpdate, UpdateOutcome, WorktreeUpdate};
+use serde::Serialize;
+use std::str::FromStr;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+	Json,
+	Junit,
+}
+
+impl FromStr for ReportFormat {
+	type Err = AppError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"json" => Ok(ReportFormat::Json),
+			"junit" => Ok(ReportFormat::Junit),
+			other => Err(AppError::UserError(format!("Unknown report format {other}. Use json or junit."))),
+		}
+	}
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SyncAction {
+	Cloned,
+	Fetched,
+	/// The checked out branch moved, `outcome` tells whether it was fast-forwarded or rebased
+	FastForwarded,
+	Skipped,
+	Failed,
+}
+
+impl SyncAction {
+	fn as_str(&self) -> &'static str {
+		match self {
+			SyncAction::Cloned => "cloned",
+			SyncAction::Fetched => "fetched",
+			SyncAction::FastForwarded => "fast-forwarded",
+			SyncAction::Skipped => "skipped",
+			SyncAction::Failed => "failed",
+		}
+	}
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ProjectSyncReport {
+	pub project: String,
+	pub action: SyncAction,
+	pub outcome: Option<UpdateOutcome>,
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub worktrees: Vec<WorktreeUpdate>,
+	pub error: Option<String>,
+	pub duration_ms: u64,
+}
+
+impl ProjectSyncReport {
+	pub fn new(project: &str, result: &Result<(SyncAction, Option<ProjectUpdate>), AppError>, duration: Duration) -> ProjectSyncReport {
+		let (action, update, error) = match result {
+			Ok((action, update)) => (*action, update.clone(), None),
+			Err(e) => (SyncAction::Failed, None, Some(e.to_string())),
+		};
+		ProjectSyncReport {
+			project: project.to_owned(),
+			action,
+			outcome: update.as_ref().map(|u| u.outcome),
+			worktrees: update.map(|u| u.worktrees).unwrap_or_default(),
+			error,
+			duration_ms: duration.as_millis() as u64,
+		}
+	}
+}
+
+pub fn render(format: ReportFormat, reports: &[ProjectSyncReport]) -> Result<String, AppError> {
+	match format {
+		ReportFormat::Json => Ok(serde_json::to_string(reports)?),
+		ReportFormat::Junit => Ok(render_junit(reports)),
+	}
+}
+
+fn render_junit(reports: &[ProjectSyncReport]) -> String {
+	let failures = reports.iter().filter(|r| r.action == SyncAction::Failed).count();
+	let skipped = reports.iter().filter(|r| r.action == SyncAction::Skipped).count();
+	let total_ms: u64 = reports.iter().map(|r| r.duration_ms).sum();
+
+	let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+	xml.push_str(&format!(
+		"<testsuite name=\"fw sync\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{}\">\n",
+		reports.len(),
+		failures,
+		skipped,
+		seconds(total_ms)
+	));
+	for report in reports {
+		xml.push_str(&format!(
+			"  <testcase classname=\"fw.sync\" name=\"{}\" time=\"{}\">\n",
+			escape_xml(&report.project),
+			seconds(report.duration_ms)
+		));
+		match report.action {
+			SyncAction::Failed => {
+				let error = escape_xml(report.error.as_deref().unwrap_or_default());
+				xml.push_str(&format!("    <failure message=\"{error}\">{error}</failure>\n"));
+			}
+			SyncAction::Skipped => xml.push_str("    <skipped/>\n"),
+			_ => (),
+		}
+		let output = match report.outcome {
+			Some(outcome) => format!("{}: {}", report.action.as_str(), outcome.describe()),
+			None => report.action.as_str().to_owned(),
+		};
+		xml.push_str(&format!("    <system-out>{output}</system-out>\n"));
+		xml.push_str("  </testcase>\n");
+	}
+	xml.push_str("</testsuite>\n");
+	xml
+}
+
+fn seconds(ms: u64) -> String {
+	format!("{:.3}", ms as f64 / 1000.0)
+}
+
+fn escape_xml(raw: &str) -> String {
+	raw
+		.replace('&', "&amp;")
+		.replace('<', "&lt;")
+		.replace('>', "&gt;")
+		.replace('"', "&quot;")
+		.replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn reports() -> Vec<ProjectSyncReport> {
+		vec![
+			ProjectSyncReport::new(
+				"fw",
+				&Ok((
+					SyncAction::FastForwarded,
+					Some(ProjectUpdate {
+						outcome: UpdateOutcome::Rebased,
+						worktrees: vec![],
+					}),
+				)),
+				Duration::from_millis(1500),
+			),
+			ProjectSyncReport::new("broken", &Err(AppError::UserError("<remote> \"gone\"".to_owned())), Duration::from_millis(20)),
+		]
+	}
+
+	#[test]
+	fn test_render_json() {
+		let json = render(ReportFormat::Json, &reports()).unwrap();
+		assert_eq!(
+			json,
+			"[{\"project\":\"fw\",\"action\":\"fast-forwarded\",\"outcome\":\"rebased\",\"error\":null,\"duration_ms\":1500},\
+			 {\"project\":\"broken\",\"action\":\"failed\",\"outcome\":null,\"error\":\"User error: <remote> \\\"gone\\\"\",\"duration_ms\":20}]"
+		);
+	}
+	#[test]
+	fn test_render_junit_escapes_failures() {
+		let xml = render(ReportFormat::Junit, &reports()).unwrap();
+		assert!(xml.contains("<testsuite name=\"fw sync\" tests=\"2\" failures=\"1\" skipped=\"0\" time=\"1.520\">"));
+		assert!(xml.contains("<testcase classname=\"fw.sync\" name=\"fw\" time=\"1.500\">"));
+		assert!(xml.contains("<failure message=\"User error: &lt;remote&gt; &quot;gone&quot;\">"));
+	}
+	#[test]
+	fn test_parse_report_format() {
+		assert_eq!("junit".parse::<ReportFormat>().unwrap(), ReportFormat::Junit);
+		assert!("xml".parse::<ReportFormat>().is_err());
+	}
+}
@@ -23,6 +23,11 @@ pub fn delete_tag(maybe_config: Result<Config, AppError>, tag_name: &str) -> Res
 	let config: Config = maybe_config?;
 	let tags: BTreeMap<String, Tag> = config.settings.tags.unwrap_or_default();
 
+	// first because it is refused for tags of a shared config dir
+	if let Some(tag) = tags.get(tag_name) {
+		config::delete_tag_config(tag_name, tag)?;
+	}
+
 	// remove tags from projects
 	for mut project in config.projects.values().cloned() {
 		let mut new_tags: BTreeSet<String> = project.tags.clone().unwrap_or_default();
@@ -31,12 +36,7 @@ pub fn delete_tag(maybe_config: Result<Config, AppError>, tag_name: &str) -> Res
 			config::write_project(&project)?;
 		}
 	}
-
-	if let Some(tag) = tags.get(tag_name) {
-		config::delete_tag_config(tag_name, tag)
-	} else {
-		Ok(())
-	}
+	Ok(())
 }
 
 fn list_all_tags(config: Config) {
@@ -71,6 +71,7 @@ pub fn create_tag(
 	after_clone: Option<String>,
 	priority: Option<u8>,
 	tag_workspace: Option<String>,
+	branch: Option<String>,
 ) -> Result<(), AppError> {
 	let config: Config = maybe_config?;
 	let tags: BTreeMap<String, Tag> = config.settings.tags.unwrap_or_default();
@@ -84,6 +85,10 @@ pub fn create_tag(
 			priority,
 			workspace: tag_workspace,
 			default: None,
+			branch,
+			clone_depth: None,
+			clone_filter: None,
+			sparse_checkout: None,
 			tag_config_path: "default".to_string(),
 		};
 		config::write_tag(&tag_name, &new_tag)?;
@@ -97,11 +102,13 @@ pub fn inspect_tag(maybe_config: Result<Config, AppError>, tag_name: &str) -> Re
 	if let Some(tag) = tags.get(tag_name) {
 		println!("{}", Paint::new(tag_name).bold().underline());
 		println!("{:<20}: {}", "config path", tag.tag_config_path);
+		println!("{:<20}: {}", "source", config.sources.describe_tag(tag_name));
 		println!("{:<20}: {}", "after workon", tag.after_workon.clone().unwrap_or_default());
 		println!("{:<20}: {}", "after clone", tag.after_clone.clone().unwrap_or_default());
 		println!("{:<20}: {}", "priority", tag.priority.map(|n| n.to_string()).unwrap_or_default());
 		println!("{:<20}: {}", "workspace", tag.workspace.clone().unwrap_or_default());
 		println!("{:<20}: {}", "default", tag.default.map(|n| n.to_string()).unwrap_or_default());
+		println!("{:<20}: {}", "branch", tag.branch.clone().unwrap_or_default());
 		println!();
 		println!("{}", Paint::new("projects".to_string()).bold().underline());
 		for project in config.projects.values().cloned() {
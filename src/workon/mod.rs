@@ -41,7 +41,7 @@ pub fn r#gen(name: &str, maybe_config: Result<config::Config, AppError>, quick:
 	let project: &Project = config
 		.projects
 		.get(name)
-		.ok_or_else(|| AppError::UserError(format!("project key {name} not found in fw.json")))?;
+		.ok_or_else(|| AppError::UserError(format!("project key {name} not found in the fw config")))?;
 	let canonical_project_path = config.actual_path_to_project(project);
 	let path = canonical_project_path
 		.to_str()
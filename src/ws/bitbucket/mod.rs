@@ -0,0 +1,238 @@
+// Bitbucket Cloud REST API 2.0: https://developer.atlassian.com/cloud/bitbucket/rest/api-group-repositories/
+// Bitbucket Server / Data Center REST API 1.0: https://developer.atlassian.com/server/bitbucket/rest/
+
+use super::{RemoteRepository, RepositoryProvider, encode_path_segment};
+use crate::errors::AppError;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+pub fn bitbucket_cloud_api(base_url: &str, token: Option<&str>) -> Result<BitbucketCloudApi, AppError> {
+	Ok(BitbucketCloudApi {
+		client: BitbucketClient::new(base_url, token),
+	})
+}
+
+pub fn bitbucket_server_api(base_url: &str, token: Option<&str>) -> Result<BitbucketServerApi, AppError> {
+	Ok(BitbucketServerApi {
+		client: BitbucketClient::new(base_url, token),
+	})
+}
+
+pub struct BitbucketCloudApi {
+	client: BitbucketClient,
+}
+
+pub struct BitbucketServerApi {
+	client: BitbucketClient,
+}
+
+struct BitbucketClient {
+	client: reqwest::blocking::Client,
+	base_url: String,
+	token: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct CloneLink {
+	name: String,
+	href: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Links {
+	clone: Vec<CloneLink>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Repository {
+	/// Bitbucket Cloud only
+	uuid: Option<String>,
+	/// Bitbucket Server only
+	id: Option<u64>,
+	slug: String,
+	links: Links,
+	#[serde(default)]
+	archived: bool,
+	/// Bitbucket Cloud only
+	language: Option<String>,
+	/// Bitbucket Cloud only
+	is_private: Option<bool>,
+	/// Bitbucket Server only
+	public: Option<bool>,
+}
+
+impl Repository {
+	/// None if the repository has no ssh clone url (e.g. ssh access is disabled).
+	fn into_remote_repository(self) -> Option<RemoteRepository> {
+		let clone_url = |name: &str| self.links.clone.iter().find(|l| l.name == name).map(|l| l.href.clone());
+		let git = clone_url("ssh")?;
+		// named http on Bitbucket Server
+		let https_git = clone_url("https").or_else(|| clone_url("http"));
+		let private = self.is_private.or(self.public.map(|public| !public));
+		Some(RemoteRepository {
+			id: self.uuid.or(self.id.map(|id| id.to_string())),
+			name: self.slug,
+			git,
+			https_git,
+			archived: self.archived,
+			topics: vec![],
+			language: self.language.filter(|l| !l.is_empty()),
+			visibility: private.map(|p| if p { "private" } else { "public" }.to_owned()),
+		})
+	}
+}
+
+/// Adds the repositories of a page, skipping archived ones unless they are asked for and the ones without ssh clone url.
+fn add_repositories(repositories: &mut Vec<RemoteRepository>, page: Vec<Repository>, include_archived: bool) {
+	for repository in page.into_iter().filter(|r| include_archived || !r.archived) {
+		let slug = repository.slug.clone();
+		match repository.into_remote_repository() {
+			Some(remote) => repositories.push(remote),
+			None => eprintln!("Skipping {slug}: it has no ssh clone url"),
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct CloudPage {
+	values: Vec<Repository>,
+	next: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ServerPage {
+	values: Vec<Repository>,
+	#[serde(rename = "isLastPage")]
+	is_last_page: bool,
+	#[serde(rename = "nextPageStart")]
+	next_page_start: Option<u64>,
+}
+
+impl BitbucketClient {
+	fn new(base_url: &str, token: Option<&str>) -> BitbucketClient {
+		BitbucketClient {
+			client: reqwest::blocking::Client::new(),
+			base_url: base_url.trim_end_matches('/').to_string(),
+			token: token.map(ToOwned::to_owned),
+		}
+	}
+
+	fn get<T: DeserializeOwned>(&self, url: &str) -> Result<T, AppError> {
+		let mut request = self.client.get(url).header("User-Agent", "fw");
+		if let Some(ref token) = self.token {
+			request = request.bearer_auth(token);
+		}
+		let res = request.send()?;
+
+		if res.status().is_success() {
+			res.json::<T>().map_err(|e| AppError::RuntimeError(format!("Failed to parse response: {e}")))
+		} else {
+			Err(AppError::RuntimeError(format!("Bad status from bitbucket {}", res.status())))
+		}
+	}
+}
+
+impl RepositoryProvider for BitbucketCloudApi {
+	fn list_repositories(&mut self, workspace: &str, include_archived: bool) -> Result<Vec<RemoteRepository>, AppError> {
+		let mut repositories: Vec<RemoteRepository> = Vec::new();
+		let mut next = Some(format!(
+			"{}/2.0/repositories/{}?pagelen=100",
+			self.client.base_url,
+			encode_path_segment(workspace)
+		));
+		while let Some(url) = next {
+			let page: CloudPage = self.client.get(&url)?;
+			add_repositories(&mut repositories, page.values, include_archived);
+			next = page.next;
+		}
+		Ok(repositories)
+	}
+}
+
+impl RepositoryProvider for BitbucketServerApi {
+	fn list_repositories(&mut self, project_key: &str, include_archived: bool) -> Result<Vec<RemoteRepository>, AppError> {
+		let mut repositories: Vec<RemoteRepository> = Vec::new();
+		let mut start = Some(0);
+		while let Some(page_start) = start {
+			let url = format!(
+				"{}/rest/api/1.0/projects/{}/repos?limit=100&start={}",
+				self.client.base_url,
+				encode_path_segment(project_key),
+				page_start
+			);
+			let page: ServerPage = self.client.get(&url)?;
+			add_repositories(&mut repositories, page.values, include_archived);
+			start = if page.is_last_page { None } else { page.next_page_start };
+		}
+		Ok(repositories)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ws::mock::{MockResponse, mock_server};
+
+	#[test]
+	fn test_cloud_follows_next_links() {
+		let (base_url, server) = mock_server(vec![
+			MockResponse::json(
+				r#"{"values":[{"slug":"fw","links":{"clone":[{"name":"https","href":"https://bitbucket.org/brocode/fw.git"},{"name":"ssh","href":"git@bitbucket.org:brocode/fw.git"}]}}],"next":"{base_url}/2.0/repositories/brocode?pagelen=100&page=2"}"#,
+			),
+			MockResponse::json(r#"{"values":[{"slug":"zeta","links":{"clone":[{"name":"ssh","href":"git@bitbucket.org:brocode/zeta.git"}]}}]}"#),
+		]);
+		let repositories = bitbucket_cloud_api(&base_url, Some("secret"))
+			.unwrap()
+			.list_repositories("brocode", false)
+			.unwrap();
+		let requests = server.join().unwrap();
+
+		assert_eq!(
+			repositories,
+			vec![
+				RemoteRepository {
+					name: "fw".to_owned(),
+					git: "git@bitbucket.org:brocode/fw.git".to_owned(),
+					https_git: Some("https://bitbucket.org/brocode/fw.git".to_owned()),
+					..RemoteRepository::default()
+				},
+				RemoteRepository {
+					name: "zeta".to_owned(),
+					git: "git@bitbucket.org:brocode/zeta.git".to_owned(),
+					..RemoteRepository::default()
+				}
+			]
+		);
+		assert!(requests[0].starts_with("GET /2.0/repositories/brocode?pagelen=100 "));
+		assert!(requests[1].starts_with("GET /2.0/repositories/brocode?pagelen=100&page=2 "));
+		assert!(requests[0].to_lowercase().contains("authorization: bearer secret"));
+	}
+	#[test]
+	fn test_server_pages_and_skips_archived() {
+		let (base_url, server) = mock_server(vec![
+			MockResponse::json(
+				r#"{"values":[{"slug":"fw","links":{"clone":[{"name":"ssh","href":"ssh://git@bitbucket.example.com:7999/bro/fw.git"}]}}],"isLastPage":false,"nextPageStart":1}"#,
+			),
+			MockResponse::json(
+				r#"{"values":[{"slug":"old","archived":true,"links":{"clone":[{"name":"ssh","href":"ssh://git@bitbucket.example.com:7999/bro/old.git"}]}}],"isLastPage":true}"#,
+			),
+		]);
+		let repositories = bitbucket_server_api(&base_url, None).unwrap().list_repositories("BRO", false).unwrap();
+		let requests = server.join().unwrap();
+
+		assert_eq!(repositories.len(), 1);
+		assert_eq!(repositories[0].git, "ssh://git@bitbucket.example.com:7999/bro/fw.git");
+		assert!(requests[1].starts_with("GET /rest/api/1.0/projects/BRO/repos?limit=100&start=1 "));
+	}
+	#[test]
+	fn test_cloud_skips_repositories_without_ssh_url_and_archived() {
+		let (base_url, server) = mock_server(vec![MockResponse::json(
+			r#"{"values":[{"slug":"http-only","links":{"clone":[{"name":"https","href":"https://bitbucket.org/bro/http-only.git"}]}},{"slug":"old","archived":true,"links":{"clone":[{"name":"ssh","href":"git@bitbucket.org:bro/old.git"}]}},{"slug":"fw","links":{"clone":[{"name":"ssh","href":"git@bitbucket.org:bro/fw.git"}]}}]}"#,
+		)]);
+		let repositories = bitbucket_cloud_api(&base_url, None).unwrap().list_repositories("bro code", false).unwrap();
+		let requests = server.join().unwrap();
+
+		assert_eq!(repositories.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(), vec!["fw"]);
+		assert!(requests[0].starts_with("GET /2.0/repositories/bro%20code?pagelen=100 "));
+	}
+}
@@ -0,0 +1,126 @@
+// Gitea (and Forgejo, which shares the api) REST API v1: https://docs.gitea.com/api/1.22/#tag/organization/operation/orgListRepos
+
+use super::{RemoteRepository, RepositoryProvider, encode_path_segment};
+use crate::errors::AppError;
+use serde::{Deserialize, Serialize};
+
+const PAGE_SIZE: usize = 50;
+
+pub fn gitea_api(base_url: &str, token: Option<&str>) -> Result<GiteaApi, AppError> {
+	let client = reqwest::blocking::Client::new();
+	Ok(GiteaApi {
+		client,
+		base_url: base_url.trim_end_matches('/').to_string(),
+		token: token.map(ToOwned::to_owned),
+	})
+}
+
+pub struct GiteaApi {
+	client: reqwest::blocking::Client,
+	base_url: String,
+	token: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Repository {
+	id: u64,
+	name: String,
+	ssh_url: String,
+	clone_url: Option<String>,
+	#[serde(default)]
+	archived: bool,
+	#[serde(default)]
+	private: bool,
+	#[serde(default)]
+	topics: Vec<String>,
+	language: Option<String>,
+}
+
+impl GiteaApi {
+	fn page_repositories(&self, org: &str, page: usize) -> Result<Vec<Repository>, AppError> {
+		let url = format!(
+			"{}/api/v1/orgs/{}/repos?page={}&limit={}",
+			self.base_url,
+			encode_path_segment(org),
+			page,
+			PAGE_SIZE
+		);
+		let mut request = self.client.get(&url).header("User-Agent", "fw");
+		if let Some(ref token) = self.token {
+			request = request.header("Authorization", format!("token {token}"));
+		}
+		let res = request.send()?;
+
+		if res.status().is_success() {
+			res
+				.json::<Vec<Repository>>()
+				.map_err(|e| AppError::RuntimeError(format!("Failed to parse response: {e}")))
+		} else {
+			Err(AppError::RuntimeError(format!("Bad status from gitea {} for org {}", res.status(), org)))
+		}
+	}
+}
+
+impl RepositoryProvider for GiteaApi {
+	fn list_repositories(&mut self, org: &str, include_archived: bool) -> Result<Vec<RemoteRepository>, AppError> {
+		let mut repositories: Vec<RemoteRepository> = Vec::new();
+		let mut page = 1;
+		loop {
+			let repos = self.page_repositories(org, page)?;
+			let last_page = repos.len() < PAGE_SIZE;
+			repositories.extend(repos.into_iter().filter(|r| include_archived || !r.archived).map(|r| RemoteRepository {
+				id: Some(r.id.to_string()),
+				name: r.name,
+				git: r.ssh_url,
+				https_git: r.clone_url,
+				archived: r.archived,
+				topics: r.topics,
+				// empty if gitea could not detect the language
+				language: r.language.filter(|l| !l.is_empty()),
+				visibility: Some(if r.private { "private" } else { "public" }.to_owned()),
+			}));
+			if last_page {
+				return Ok(repositories);
+			}
+			page += 1;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ws::mock::{MockResponse, mock_server};
+
+	#[test]
+	fn test_list_repositories_stops_at_short_page() {
+		let full_page: Vec<String> = (0..PAGE_SIZE)
+			.map(|i| {
+				format!(
+					r#"{{"id":{i},"name":"r{i}","ssh_url":"git@gitea.example.com:brocode/r{i}.git","archived":{}}}"#,
+					i == 0
+				)
+			})
+			.collect();
+		let (base_url, server) = mock_server(vec![
+			MockResponse::json(&format!("[{}]", full_page.join(","))),
+			MockResponse::json(r#"[{"id":99,"name":"fw","ssh_url":"git@gitea.example.com:brocode/fw.git","archived":false}]"#),
+		]);
+		let repositories = gitea_api(&base_url, Some("secret")).unwrap().list_repositories("brocode", false).unwrap();
+		let requests = server.join().unwrap();
+
+		assert_eq!(repositories.len(), PAGE_SIZE);
+		assert_eq!(
+			repositories.last(),
+			Some(&RemoteRepository {
+				id: Some("99".to_owned()),
+				name: "fw".to_owned(),
+				git: "git@gitea.example.com:brocode/fw.git".to_owned(),
+				visibility: Some("public".to_owned()),
+				..RemoteRepository::default()
+			})
+		);
+		assert!(requests[1].starts_with("GET /api/v1/orgs/brocode/repos?page=2&limit=50 "));
+		assert!(requests[0].to_lowercase().contains("authorization: token secret"));
+	}
+}
@@ -1,43 +1,172 @@
 // some of the code is from here: https://github.com/mgattozzi/github-rs/tree/master/github-gql-rs
 // this package seems unmaintained at the moment. Also it is basically just a small http client wrapper.
 
+use super::{RemoteRepository, RepositoryProvider};
 use crate::errors::AppError;
+use reqwest::StatusCode;
+use reqwest::blocking::{RequestBuilder, Response};
+use reqwest::header::HeaderMap;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::thread;
+use std::time::Duration;
 
-pub fn github_api(token: &str) -> Result<GithubApi, AppError> {
+pub fn github_api(api_url: &str, token: &str, ssh_host: Option<&str>) -> Result<GithubApi, AppError> {
 	let client = reqwest::blocking::Client::new();
 	Ok(GithubApi {
 		client,
+		api_url: api_url.to_string(),
 		token: token.to_string(),
+		ssh_host: ssh_host.map(ToOwned::to_owned),
 	})
 }
 
 pub struct GithubApi {
 	client: reqwest::blocking::Client,
+	api_url: String,
 	token: String,
+	/// Replaces the host in the ssh clone urls
+	ssh_host: Option<String>,
 }
 
-struct PageResult {
-	repository_names: Vec<String>,
-	next_cursor: Option<String>,
+/// Whose repositories are imported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RepositorySource {
+	Organization(String),
+	/// Repositories owned by the user
+	User {
+		login: String,
+		include_forks: bool,
+	},
+	/// Repositories starred by the user
+	Starred(String),
+	/// Repositories the team has access to
+	Team {
+		org: String,
+		slug: String,
+	},
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct OrganizationQueryResponse {
-	data: OrganizationQueryResponseData,
+impl RepositorySource {
+	/// Parses `ORG/TEAM`.
+	pub fn team(org_and_team: &str) -> Result<RepositorySource, AppError> {
+		match org_and_team.split_once('/') {
+			Some((org, slug)) if !org.is_empty() && !slug.is_empty() => Ok(RepositorySource::Team {
+				org: org.to_owned(),
+				slug: slug.to_owned(),
+			}),
+			_ => Err(AppError::UserError(format!("Team {org_and_team} must be given as ORG/TEAM"))),
+		}
+	}
+
+	/// The projects directory the imported projects are written to.
+	pub fn config_path(&self) -> String {
+		match self {
+			RepositorySource::Organization(org) => org.clone(),
+			RepositorySource::User { login, .. } | RepositorySource::Starred(login) => login.clone(),
+			RepositorySource::Team { org, slug } => format!("{org}/{slug}"),
+		}
+	}
+
+	fn describe(&self) -> String {
+		match self {
+			RepositorySource::Organization(org) => format!("organization {org}"),
+			RepositorySource::User { login, .. } => format!("user {login}"),
+			RepositorySource::Starred(login) => format!("stars of user {login}"),
+			RepositorySource::Team { org, slug } => format!("team {org}/{slug}"),
+		}
+	}
+
+	/// The query for one page of repositories. Logins, slug and cursor are passed as `variables`.
+	fn query(&self) -> String {
+		let page = format!("{{nodes {REPOSITORY_FIELDS} pageInfo {{endCursor hasNextPage}}}}");
+		match self {
+			RepositorySource::Organization(_) => {
+				format!("query($org: String!, $after: String) {{organization(login: $org) {{repositories(first: 100, after: $after) {page}}}}}")
+			}
+			RepositorySource::User { .. } => format!(
+				"query($login: String!, $isFork: Boolean, $after: String) {{user(login: $login) {{repositories(ownerAffiliations: [OWNER], isFork: $isFork, first: 100, after: $after) {page}}}}}"
+			),
+			RepositorySource::Starred(_) => {
+				format!("query($login: String!, $after: String) {{user(login: $login) {{starredRepositories(first: 100, after: $after) {page}}}}}")
+			}
+			RepositorySource::Team { .. } => format!(
+				"query($org: String!, $slug: String!, $after: String) {{organization(login: $org) {{team(slug: $slug) {{repositories(first: 100, after: $after) {page}}}}}}}"
+			),
+		}
+	}
+
+	fn variables(&self, after: Option<String>) -> serde_json::Value {
+		match self {
+			RepositorySource::Organization(org) => serde_json::json!({"org": org, "after": after}),
+			// null includes forks
+			RepositorySource::User { login, include_forks } => {
+				serde_json::json!({"login": login, "isFork": if *include_forks { None } else { Some(false) }, "after": after})
+			}
+			RepositorySource::Starred(login) => serde_json::json!({"login": login, "after": after}),
+			RepositorySource::Team { org, slug } => serde_json::json!({"org": org, "slug": slug, "after": after}),
+		}
+	}
+
+	/// Where the repository connection is located in the data of the response.
+	fn connection_pointer(&self) -> &'static str {
+		match self {
+			RepositorySource::Organization(_) => "/organization/repositories",
+			RepositorySource::User { .. } => "/user/repositories",
+			RepositorySource::Starred(_) => "/user/starredRepositories",
+			RepositorySource::Team { .. } => "/organization/team/repositories",
+		}
+	}
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct OrganizationQueryResponseData {
-	organization: OrganizationRepositoriesResponseData,
+const PULL_REQUEST_SEARCH: &str = "query($query: String!, $after: String) {search(query: $query, type: ISSUE, first: 100, after: $after) {nodes {... on PullRequest {number title url isDraft createdAt author {login} repository {nameWithOwner}}} pageInfo {endCursor hasNextPage}}}";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PullRequest {
+	pub number: u64,
+	pub title: String,
+	pub url: String,
+	#[serde(alias = "isDraft")]
+	pub is_draft: bool,
+	#[serde(alias = "createdAt")]
+	pub created_at: String,
+	/// None for deleted users
+	pub author: Option<Author>,
+	pub repository: PullRequestRepository,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct OrganizationRepositoriesResponseData {
-	repositories: RepositoriesResponseData,
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Author {
+	pub login: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PullRequestRepository {
+	#[serde(alias = "nameWithOwner")]
+	pub name_with_owner: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct SearchData {
+	search: SearchResult,
+}
+
+#[derive(Deserialize, Debug)]
+struct SearchResult {
+	/// Issues match the search as well but come back as empty objects
+	#[serde(deserialize_with = "pull_requests_only")]
+	nodes: Vec<Option<PullRequest>>,
+	#[serde(rename = "pageInfo")]
+	page_info: PageInfo,
 }
 
+fn pull_requests_only<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<Option<PullRequest>>, D::Error> {
+	let nodes: Vec<serde_json::Value> = Vec::deserialize(deserializer)?;
+	Ok(nodes.into_iter().map(|node| serde_json::from_value(node).ok()).collect())
+}
+
+const REPOSITORY_FIELDS: &str = "{id, name, sshUrl, url, isArchived, primaryLanguage {name}, visibility, repositoryTopics(first: 20) {nodes {topic {name}}}}";
+
 #[derive(Serialize, Deserialize, Debug)]
 struct RepositoriesResponseData {
 	nodes: Vec<Repository>,
@@ -47,82 +176,513 @@ struct RepositoriesResponseData {
 
 #[derive(Serialize, Deserialize, Debug)]
 struct Repository {
+	id: String,
 	name: String,
+	#[serde(rename = "sshUrl")]
+	ssh_url: String,
+	url: String,
 	#[serde(rename = "isArchived")]
 	is_archived: bool,
+	#[serde(rename = "primaryLanguage")]
+	primary_language: Option<Language>,
+	visibility: Option<String>,
+	#[serde(rename = "repositoryTopics")]
+	repository_topics: Option<RepositoryTopics>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Language {
+	name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct RepositoryTopics {
+	nodes: Vec<RepositoryTopic>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct RepositoryTopic {
+	topic: Topic,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Topic {
+	name: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 struct PageInfo {
 	#[serde(rename = "endCursor")]
-	end_cursor: String,
+	end_cursor: Option<String>,
 	#[serde(rename = "hasNextPage")]
 	has_next_page: bool,
 }
 
-impl GithubApi {
-	fn query<T: DeserializeOwned>(&self, query: &str) -> Result<T, AppError> {
-		//escaping new lines and quotation marks for json
-		let mut escaped = query.to_string();
-		escaped = escaped.replace('\n', "\\n");
-		escaped = escaped.replace('\"', "\\\"");
-
-		let mut q = String::from("{ \"query\": \"");
-		q.push_str(&escaped);
-		q.push_str("\" }");
-
-		let res = self
-			.client
-			.post("https://api.github.com/graphql")
-			.body(reqwest::blocking::Body::from(q))
-			.header("Content-Type", "application/json")
-			.header("User-Agent", "github-rs")
-			.header("Authorization", format!("token {}", self.token))
-			.send()?;
-
-		if res.status().is_success() {
-			res.json::<T>().map_err(|e| AppError::RuntimeError(format!("Failed to parse response: {e}")))
-		} else {
-			Err(AppError::RuntimeError(format!("Bad status from github {}", res.status())))
-		}
-	}
-
-	pub fn list_repositories(&mut self, org: &str, include_archived: bool) -> Result<Vec<String>, AppError> {
-		let initial_page = self.page_repositories(org, None, include_archived)?;
-		let mut initial_names = initial_page.repository_names;
-
-		let mut next: Option<String> = initial_page.next_cursor;
-		while next.is_some() {
-			let next_repos = self.page_repositories(org, next.clone(), include_archived)?;
-			initial_names.extend(next_repos.repository_names);
-			next = next_repos.next_cursor;
-		}
-
-		Ok(initial_names)
-	}
-	fn page_repositories(&mut self, org: &str, after: Option<String>, include_archived: bool) -> Result<PageResult, AppError> {
-		let after_refinement = after.map(|a| format!(", after:\"{a}\"")).unwrap_or_else(|| "".to_owned());
-		let response: OrganizationQueryResponse = self.query(
-			&("query {organization(login: \"".to_owned()
-				+ org
-				+ "\"){repositories(first: 100"
-				+ &after_refinement
-				+ ") {nodes {name, isArchived} pageInfo {endCursor hasNextPage}}}}"),
-		)?;
-		let repositories: Vec<Repository> = response.data.organization.repositories.nodes;
-		let repo_names: Vec<String> = repositories
-			.into_iter()
-			.filter(|r| include_archived || !r.is_archived)
-			.map(|r| r.name)
-			.collect();
-
-		Ok(PageResult {
-			repository_names: repo_names,
-			next_cursor: if response.data.organization.repositories.page_info.has_next_page {
-				Some(response.data.organization.repositories.page_info.end_cursor)
-			} else {
-				None
+#[derive(Deserialize, Debug)]
+struct RestRepository {
+	node_id: String,
+	name: String,
+	ssh_url: String,
+	clone_url: String,
+	#[serde(default)]
+	archived: bool,
+	#[serde(default)]
+	topics: Vec<String>,
+	language: Option<String>,
+	visibility: Option<String>,
+}
+
+impl RestRepository {
+	fn into_remote_repository(self, ssh_host: Option<&str>) -> RemoteRepository {
+		RemoteRepository {
+			id: Some(self.node_id),
+			git: match ssh_host {
+				Some(host) => with_ssh_host(&self.ssh_url, host),
+				None => self.ssh_url,
+			},
+			https_git: Some(self.clone_url),
+			name: self.name,
+			archived: self.archived,
+			topics: self.topics,
+			language: self.language,
+			visibility: self.visibility,
+		}
+	}
+}
+
+impl Repository {
+	fn into_remote_repository(self, ssh_host: Option<&str>) -> RemoteRepository {
+		RemoteRepository {
+			id: Some(self.id),
+			git: match ssh_host {
+				Some(host) => with_ssh_host(&self.ssh_url, host),
+				None => self.ssh_url,
 			},
-		})
+			https_git: Some(format!("{}.git", self.url)),
+			name: self.name,
+			archived: self.is_archived,
+			topics: self
+				.repository_topics
+				.map(|t| t.nodes.into_iter().map(|n| n.topic.name).collect())
+				.unwrap_or_default(),
+			language: self.primary_language.map(|l| l.name),
+			visibility: self.visibility.map(|v| v.to_lowercase()),
+		}
+	}
+}
+
+/// Host and path of an ssh (scp-like or ssh://) or https clone url.
+fn split_url(git: &str) -> Option<(&str, &str)> {
+	let (host, path) = match git.split_once("://") {
+		Some((_, host_and_path)) => host_and_path.split_once('/')?,
+		None => git.split_once(':')?,
+	};
+	let host = host.rsplit('@').next()?;
+	Some((host.split(':').next()?, path))
+}
+
+/// The host of the clone url without user and port.
+pub fn repository_host(git: &str) -> Option<&str> {
+	split_url(git).map(|(host, _)| host)
+}
+
+/// Owner and name of the repository from its ssh (scp-like or ssh://) or https clone url.
+pub fn repository_path(git: &str) -> Option<(String, String)> {
+	let path = split_url(git)?.1;
+	let path = path.trim_end_matches('/');
+	let path = path.strip_suffix(".git").unwrap_or(path);
+	let (owner, name) = path.rsplit_once('/')?;
+	let owner = owner.rsplit('/').next()?;
+	if owner.is_empty() || name.is_empty() {
+		return None;
+	}
+	Some((owner.to_owned(), name.to_owned()))
+}
+
+/// The whole path of the repository from its clone url, lowercase and without `.git`, e.g. `brocode/infra/fw`.
+pub fn repository_full_path(git: &str) -> Option<String> {
+	let path = split_url(git)?.1.trim_matches('/');
+	let path = path.strip_suffix(".git").unwrap_or(path);
+	if path.is_empty() { None } else { Some(path.to_lowercase()) }
+}
+
+/// Replaces the host of an scp-like ssh url (git@github.com:brocode/fw.git).
+fn with_ssh_host(ssh_url: &str, host: &str) -> String {
+	match ssh_url.split_once(':') {
+		Some((user_and_host, path)) => match user_and_host.split_once('@') {
+			Some((user, _)) => format!("{user}@{host}:{path}"),
+			None => format!("{host}:{path}"),
+		},
+		None => ssh_url.to_owned(),
+	}
+}
+
+const MAX_RETRIES: u32 = 3;
+
+#[derive(Serialize, Debug)]
+struct GraphQlRequest<'a> {
+	query: &'a str,
+	variables: serde_json::Value,
+}
+
+#[derive(Deserialize, Debug)]
+struct GraphQlResponse<T> {
+	data: Option<T>,
+	#[serde(default)]
+	errors: Vec<GraphQlErrorMessage>,
+}
+
+#[derive(Deserialize, Debug)]
+struct GraphQlErrorMessage {
+	message: String,
+	#[serde(rename = "type")]
+	error_type: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+struct ForkRequest<'a> {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	organization: Option<&'a str>,
+}
+
+#[derive(Serialize, Debug)]
+struct CreateRepositoryRequest<'a> {
+	name: &'a str,
+	private: bool,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	description: Option<&'a str>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum RateLimit {
+	/// Exhausted until the reset time (unix time in seconds). Not worth waiting for.
+	Primary(Option<u64>),
+	/// Too many requests in a short time, retry after the duration
+	Secondary(Duration),
+}
+
+/// See https://docs.github.com/en/rest/using-the-rest-api/rate-limits-for-the-rest-api#exceeding-the-rate-limit
+fn rate_limit(status: StatusCode, headers: &HeaderMap) -> Option<RateLimit> {
+	if status != StatusCode::FORBIDDEN && status != StatusCode::TOO_MANY_REQUESTS {
+		return None;
+	}
+	let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).and_then(|v| v.parse::<u64>().ok());
+	if let Some(retry_after) = header("retry-after") {
+		Some(RateLimit::Secondary(Duration::from_secs(retry_after)))
+	} else if header("x-ratelimit-remaining") == Some(0) {
+		Some(RateLimit::Primary(header("x-ratelimit-reset")))
+	} else if status == StatusCode::TOO_MANY_REQUESTS {
+		Some(RateLimit::Secondary(Duration::from_secs(60)))
+	} else {
+		None
+	}
+}
+
+fn rate_limit_reset(headers: &HeaderMap) -> Option<u64> {
+	headers.get("x-ratelimit-reset").and_then(|v| v.to_str().ok()).and_then(|v| v.parse().ok())
+}
+
+impl GithubApi {
+	/// Sends the request, waiting for secondary rate limits. Fails on an exhausted rate limit and on bad status codes.
+	fn send(&self, request: impl Fn() -> RequestBuilder) -> Result<Response, AppError> {
+		let mut retries = 0;
+		loop {
+			let res = request()
+				.header("User-Agent", "github-rs")
+				.header("Authorization", format!("token {}", self.token))
+				.send()?;
+
+			match rate_limit(res.status(), res.headers()) {
+				Some(RateLimit::Secondary(wait)) if retries < MAX_RETRIES => {
+					retries += 1;
+					eprintln!("Hit a secondary rate limit of github, retrying in {}s", wait.as_secs());
+					thread::sleep(wait);
+				}
+				Some(RateLimit::Secondary(_)) => return Err(AppError::RateLimited { reset: None }),
+				Some(RateLimit::Primary(reset)) => return Err(AppError::RateLimited { reset }),
+				None if res.status().is_success() => return Ok(res),
+				None => return Err(AppError::RuntimeError(format!("Bad status from github {}", res.status()))),
+			}
+		}
+	}
+
+	fn query<T: DeserializeOwned>(&self, query: &str, variables: serde_json::Value) -> Result<T, AppError> {
+		let request = GraphQlRequest { query, variables };
+		let res = self.send(|| self.client.post(&self.api_url).json(&request))?;
+		let reset = rate_limit_reset(res.headers());
+		let response = res
+			.json::<GraphQlResponse<T>>()
+			.map_err(|e| AppError::RuntimeError(format!("Failed to parse response: {e}")))?;
+		if response.errors.iter().any(|e| e.error_type.as_deref() == Some("RATE_LIMITED")) {
+			return Err(AppError::RateLimited { reset });
+		}
+		if !response.errors.is_empty() {
+			return Err(AppError::GraphQlError(response.errors.into_iter().map(|e| e.message).collect()));
+		}
+		response
+			.data
+			.ok_or_else(|| AppError::RuntimeError("Response from github contains neither data nor errors".to_owned()))
+	}
+
+	/// The base url of the REST api. GitHub has no GraphQL mutation for forks.
+	fn rest_url(&self) -> String {
+		match self.api_url.strip_suffix("/api/graphql") {
+			Some(enterprise) => format!("{enterprise}/api/v3"),
+			None => self.api_url.trim_end_matches("/graphql").to_owned(),
+		}
+	}
+
+	/// Forks the repository into the account of the token owner or into the organization. GitHub creates the fork
+	/// asynchronously, it can take a moment until it can be fetched.
+	pub fn create_fork(&self, owner: &str, repository: &str, organization: Option<&str>) -> Result<RemoteRepository, AppError> {
+		let url = format!("{}/repos/{}/{}/forks", self.rest_url(), owner, repository);
+		let body = ForkRequest { organization };
+		let res = self.send(|| self.client.post(&url).header("Accept", "application/vnd.github+json").json(&body))?;
+		let fork = res
+			.json::<RestRepository>()
+			.map_err(|e| AppError::RuntimeError(format!("Failed to parse response: {e}")))?;
+		Ok(fork.into_remote_repository(self.ssh_host.as_deref()))
+	}
+
+	/// Creates an empty repository in the account of the token owner or in the organization.
+	pub fn create_repository(&self, organization: Option<&str>, name: &str, private: bool, description: Option<&str>) -> Result<RemoteRepository, AppError> {
+		let url = match organization {
+			Some(org) => format!("{}/orgs/{}/repos", self.rest_url(), org),
+			None => format!("{}/user/repos", self.rest_url()),
+		};
+		let body = CreateRepositoryRequest { name, private, description };
+		let res = self.send(|| self.client.post(&url).header("Accept", "application/vnd.github+json").json(&body))?;
+		let repository = res
+			.json::<RestRepository>()
+			.map_err(|e| AppError::RuntimeError(format!("Failed to parse response: {e}")))?;
+		Ok(repository.into_remote_repository(self.ssh_host.as_deref()))
+	}
+
+	pub fn list_source_repositories(&mut self, source: &RepositorySource, include_archived: bool) -> Result<Vec<RemoteRepository>, AppError> {
+		let mut repositories: Vec<RemoteRepository> = Vec::new();
+		let mut after: Option<String> = None;
+		loop {
+			let page = self.page_repositories(source, after)?;
+			repositories.extend(
+				page
+					.nodes
+					.into_iter()
+					.filter(|r| include_archived || !r.is_archived)
+					.map(|r| r.into_remote_repository(self.ssh_host.as_deref())),
+			);
+			if !page.page_info.has_next_page || page.page_info.end_cursor.is_none() {
+				return Ok(repositories);
+			}
+			after = page.page_info.end_cursor;
+		}
+	}
+
+	fn page_repositories(&mut self, source: &RepositorySource, after: Option<String>) -> Result<RepositoriesResponseData, AppError> {
+		let response: serde_json::Value = self.query(&source.query(), source.variables(after))?;
+		match response.pointer(source.connection_pointer()) {
+			Some(connection) if !connection.is_null() => Ok(serde_json::from_value(connection.clone())?),
+			_ => Err(AppError::UserError(format!("Could not find the {} on GitHub", source.describe()))),
+		}
+	}
+
+	/// Searches open pull requests. `qualifiers` are github search qualifiers like `author:@me repo:brocode/fw`.
+	pub fn search_pull_requests(&self, qualifiers: &str) -> Result<Vec<PullRequest>, AppError> {
+		let search_query = format!("is:pr is:open {qualifiers}");
+		let mut pull_requests = Vec::new();
+		let mut after: Option<String> = None;
+		loop {
+			let data: SearchData = self.query(PULL_REQUEST_SEARCH, serde_json::json!({"query": search_query, "after": after}))?;
+			pull_requests.extend(data.search.nodes.into_iter().flatten());
+			if !data.search.page_info.has_next_page || data.search.page_info.end_cursor.is_none() {
+				return Ok(pull_requests);
+			}
+			after = data.search.page_info.end_cursor;
+		}
+	}
+}
+
+impl RepositoryProvider for GithubApi {
+	fn list_repositories(&mut self, org: &str, include_archived: bool) -> Result<Vec<RemoteRepository>, AppError> {
+		self.list_source_repositories(&RepositorySource::Organization(org.to_owned()), include_archived)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ws::mock::{MockResponse, mock_server};
+
+	const ORGANIZATION_PAGE: &str = r#"{"data":{"organization":{"repositories":{"nodes":[{"id":"R_1","name":"fw","sshUrl":"git@github.com:brocode/fw.git","url":"https://github.com/brocode/fw","isArchived":false}],"pageInfo":{"endCursor":"c1","hasNextPage":false}}}}}"#;
+
+	#[test]
+	fn test_user_query_excludes_forks() {
+		let source = RepositorySource::User {
+			login: "bomgar".to_owned(),
+			include_forks: false,
+		};
+		assert!(source.query().contains("isFork: $isFork"));
+		assert_eq!(
+			source.variables(Some("c1".to_owned())),
+			serde_json::json!({"login": "bomgar", "isFork": false, "after": "c1"})
+		);
+	}
+	#[test]
+	fn test_parse_team() {
+		assert_eq!(
+			RepositorySource::team("brocode/core").unwrap(),
+			RepositorySource::Team {
+				org: "brocode".to_owned(),
+				slug: "core".to_owned()
+			}
+		);
+		assert!(RepositorySource::team("brocode").is_err());
+	}
+	#[test]
+	fn test_repository_with_topics() {
+		let repository: Repository = serde_json::from_str(
+			r#"{"id":"R_1","name":"fw","sshUrl":"git@github.com:brocode/fw.git","url":"https://github.com/brocode/fw","isArchived":false,"primaryLanguage":{"name":"Rust"},"visibility":"PUBLIC","repositoryTopics":{"nodes":[{"topic":{"name":"cli"}}]}}"#,
+		)
+		.unwrap();
+		let remote = repository.into_remote_repository(None);
+		assert_eq!(remote.git, "git@github.com:brocode/fw.git");
+		assert_eq!(remote.https_git, Some("https://github.com/brocode/fw.git".to_owned()));
+		assert_eq!(remote.topics, vec!["cli".to_owned()]);
+		assert_eq!(remote.language, Some("Rust".to_owned()));
+		assert_eq!(remote.visibility, Some("public".to_owned()));
+	}
+	#[test]
+	fn test_custom_ssh_host() {
+		assert_eq!(
+			with_ssh_host("git@github.example.com:brocode/fw.git", "github-work"),
+			"git@github-work:brocode/fw.git"
+		);
+	}
+	#[test]
+	fn test_query_is_json_encoded() {
+		let (base_url, server) = mock_server(vec![MockResponse::json(ORGANIZATION_PAGE)]);
+		let repositories = github_api(&base_url, "secret", None).unwrap().list_repositories("brocode", false).unwrap();
+		let request = server.join().unwrap().remove(0);
+
+		assert_eq!(repositories.len(), 1);
+		let body: serde_json::Value = serde_json::from_str(request.lines().last().unwrap()).unwrap();
+		assert_eq!(body["query"].as_str().unwrap(), RepositorySource::Organization("brocode".to_owned()).query());
+		assert_eq!(body["variables"], serde_json::json!({"org": "brocode", "after": null}));
+		assert!(request.to_lowercase().contains("authorization: token secret"));
+	}
+	#[test]
+	fn test_graphql_errors() {
+		let (base_url, server) = mock_server(vec![MockResponse::json(
+			r#"{"data":{"organization":null},"errors":[{"type":"NOT_FOUND","message":"Could not resolve to an Organization with the login of 'nope'."}]}"#,
+		)]);
+		let result = github_api(&base_url, "secret", None).unwrap().list_repositories("nope", false);
+		server.join().unwrap();
+
+		match result {
+			Err(AppError::GraphQlError(messages)) => assert_eq!(messages, vec!["Could not resolve to an Organization with the login of 'nope'."]),
+			other => panic!("Expected a GraphQL error, got {other:?}"),
+		}
+	}
+	#[test]
+	fn test_retries_after_secondary_rate_limit() {
+		let (base_url, server) = mock_server(vec![
+			MockResponse::json(r#"{"message":"You have exceeded a secondary rate limit."}"#)
+				.with_status(403)
+				.with_header("retry-after", "0"),
+			MockResponse::json(ORGANIZATION_PAGE),
+		]);
+		let repositories = github_api(&base_url, "secret", None).unwrap().list_repositories("brocode", false).unwrap();
+
+		assert_eq!(server.join().unwrap().len(), 2);
+		assert_eq!(repositories.len(), 1);
+	}
+	#[test]
+	fn test_primary_rate_limit() {
+		let (base_url, server) = mock_server(vec![
+			MockResponse::json(r#"{"message":"API rate limit exceeded"}"#)
+				.with_status(403)
+				.with_header("x-ratelimit-remaining", "0")
+				.with_header("x-ratelimit-reset", "1700000000"),
+		]);
+		let result = github_api(&base_url, "secret", None).unwrap().list_repositories("brocode", false);
+		server.join().unwrap();
+
+		assert!(matches!(result, Err(AppError::RateLimited { reset: Some(1700000000) })));
+	}
+	#[test]
+	fn test_rate_limited_graphql_error() {
+		let (base_url, server) = mock_server(vec![
+			MockResponse::json(r#"{"errors":[{"type":"RATE_LIMITED","message":"API rate limit exceeded"}]}"#).with_header("x-ratelimit-reset", "1700000000"),
+		]);
+		let result = github_api(&base_url, "secret", None).unwrap().list_repositories("brocode", false);
+		server.join().unwrap();
+
+		assert!(matches!(result, Err(AppError::RateLimited { reset: Some(1700000000) })));
+	}
+	#[test]
+	fn test_repository_path() {
+		let fw = Some(("brocode".to_owned(), "fw".to_owned()));
+		assert_eq!(repository_path("git@github.com:brocode/fw.git"), fw);
+		assert_eq!(repository_path("https://github.com/brocode/fw"), fw);
+		assert_eq!(repository_path("ssh://git@github.example.com:22/brocode/fw.git"), fw);
+		assert_eq!(repository_path("/home/brocode/fw"), None);
+	}
+	#[test]
+	fn test_create_fork_in_organization() {
+		let (base_url, server) = mock_server(vec![
+			MockResponse::json(
+				r#"{"node_id":"R_2","name":"fw","ssh_url":"git@github.com:bros/fw.git","clone_url":"https://github.com/bros/fw.git","visibility":"public"}"#,
+			)
+			.with_status(202),
+		]);
+		let fork = github_api(&format!("{base_url}/graphql"), "secret", None)
+			.unwrap()
+			.create_fork("brocode", "fw", Some("bros"))
+			.unwrap();
+		let request = server.join().unwrap().remove(0);
+
+		assert_eq!(fork.git, "git@github.com:bros/fw.git");
+		assert_eq!(fork.id, Some("R_2".to_owned()));
+		assert!(request.starts_with("POST /repos/brocode/fw/forks "));
+		assert!(request.ends_with(r#"{"organization":"bros"}"#));
+	}
+	#[test]
+	fn test_create_repository_for_user() {
+		let (base_url, server) = mock_server(vec![
+			MockResponse::json(
+				r#"{"node_id":"R_3","name":"new-service","ssh_url":"git@github.com:bomgar/new-service.git","clone_url":"https://github.com/bomgar/new-service.git"}"#,
+			)
+			.with_status(201),
+		]);
+		let repository = github_api(&format!("{base_url}/graphql"), "secret", Some("github-work"))
+			.unwrap()
+			.create_repository(None, "new-service", true, None)
+			.unwrap();
+		let request = server.join().unwrap().remove(0);
+
+		assert_eq!(repository.git, "git@github-work:bomgar/new-service.git");
+		assert!(request.starts_with("POST /user/repos "));
+		assert!(request.ends_with(r#"{"name":"new-service","private":true}"#));
+	}
+	#[test]
+	fn test_repository_host() {
+		assert_eq!(repository_host("git@github.com:brocode/fw.git"), Some("github.com"));
+		assert_eq!(repository_host("https://github.example.com/brocode/fw"), Some("github.example.com"));
+		assert_eq!(repository_host("ssh://git@github.example.com:22/brocode/fw.git"), Some("github.example.com"));
+	}
+	#[test]
+	fn test_search_pull_requests_skips_issues() {
+		let (base_url, server) = mock_server(vec![MockResponse::json(
+			r#"{"data":{"search":{"nodes":[{"number":1,"title":"Fix sync","url":"https://github.com/brocode/fw/pull/1","isDraft":false,"createdAt":"2024-01-01T00:00:00Z","author":{"login":"bomgar"},"repository":{"nameWithOwner":"brocode/fw"}},{}],"pageInfo":{"endCursor":null,"hasNextPage":false}}}}"#,
+		)]);
+		let pull_requests = github_api(&base_url, "secret", None)
+			.unwrap()
+			.search_pull_requests("author:@me repo:brocode/fw")
+			.unwrap();
+		let request = server.join().unwrap().remove(0);
+
+		assert_eq!(pull_requests.len(), 1);
+		assert_eq!(pull_requests[0].repository.name_with_owner, "brocode/fw");
+		let body: serde_json::Value = serde_json::from_str(request.lines().last().unwrap()).unwrap();
+		assert_eq!(body["variables"]["query"], "is:pr is:open author:@me repo:brocode/fw");
 	}
 }
@@ -0,0 +1,160 @@
+// GitLab REST API v4: https://docs.gitlab.com/ee/api/groups.html#list-a-groups-projects
+
+use super::{RemoteRepository, RepositoryProvider, encode_path_segment};
+use crate::errors::AppError;
+use serde::{Deserialize, Serialize};
+
+pub fn gitlab_api(base_url: &str, token: Option<&str>) -> Result<GitlabApi, AppError> {
+	let client = reqwest::blocking::Client::new();
+	Ok(GitlabApi {
+		client,
+		base_url: base_url.trim_end_matches('/').to_string(),
+		token: token.map(ToOwned::to_owned),
+	})
+}
+
+pub struct GitlabApi {
+	client: reqwest::blocking::Client,
+	base_url: String,
+	token: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GitlabProject {
+	pub id: u64,
+	pub path: String,
+	pub path_with_namespace: String,
+	pub ssh_url_to_repo: String,
+	pub http_url_to_repo: String,
+	#[serde(default)]
+	pub archived: bool,
+	#[serde(default)]
+	pub topics: Vec<String>,
+	pub visibility: Option<String>,
+}
+
+struct PageResult {
+	projects: Vec<GitlabProject>,
+	next_page: Option<String>,
+}
+
+impl GitlabApi {
+	/// All projects of the group including the projects of its subgroups.
+	pub fn list_group_projects(&self, group: &str, include_archived: bool) -> Result<Vec<GitlabProject>, AppError> {
+		let mut projects: Vec<GitlabProject> = Vec::new();
+		let mut next: Option<String> = Some("1".to_owned());
+		while let Some(page) = next {
+			let result = self.page_group_projects(group, &page)?;
+			projects.extend(result.projects.into_iter().filter(|p| include_archived || !p.archived));
+			next = result.next_page;
+		}
+		Ok(projects)
+	}
+
+	fn page_group_projects(&self, group: &str, page: &str) -> Result<PageResult, AppError> {
+		let url = format!(
+			"{}/api/v4/groups/{}/projects?include_subgroups=true&per_page=100&page={}",
+			self.base_url,
+			// group paths like `parent/child` are a single segment as the id in the url
+			encode_path_segment(group),
+			page
+		);
+		let mut request = self.client.get(&url).header("User-Agent", "fw");
+		if let Some(ref token) = self.token {
+			request = request.header("PRIVATE-TOKEN", token);
+		}
+		let res = request.send()?;
+
+		if !res.status().is_success() {
+			return Err(AppError::RuntimeError(format!("Bad status from gitlab {} for group {}", res.status(), group)));
+		}
+		// the header is empty on the last page
+		let next_page = res
+			.headers()
+			.get("X-Next-Page")
+			.and_then(|h| h.to_str().ok())
+			.map(|h| h.trim().to_owned())
+			.filter(|h| !h.is_empty());
+		let projects = res
+			.json::<Vec<GitlabProject>>()
+			.map_err(|e| AppError::RuntimeError(format!("Failed to parse response: {e}")))?;
+		Ok(PageResult { projects, next_page })
+	}
+}
+
+/// The path of the project relative to the group with `/` replaced by `-`, so that projects with the same path in
+/// different subgroups get different names: `brocode/infra/sub/old` in the group `brocode/infra` is `sub-old`.
+fn project_name(group: &str, path_with_namespace: &str) -> String {
+	let group = group.trim_matches('/');
+	let relative = path_with_namespace
+		.get(..group.len())
+		.filter(|prefix| prefix.eq_ignore_ascii_case(group))
+		.and_then(|_| path_with_namespace[group.len()..].strip_prefix('/'))
+		.unwrap_or(path_with_namespace);
+	relative.replace('/', "-")
+}
+
+impl RepositoryProvider for GitlabApi {
+	fn list_repositories(&mut self, group: &str, include_archived: bool) -> Result<Vec<RemoteRepository>, AppError> {
+		Ok(
+			self
+				.list_group_projects(group, include_archived)?
+				.into_iter()
+				.map(|p| RemoteRepository {
+					id: Some(p.id.to_string()),
+					name: project_name(group, &p.path_with_namespace),
+					git: p.ssh_url_to_repo,
+					https_git: Some(p.http_url_to_repo),
+					archived: p.archived,
+					topics: p.topics,
+					language: None,
+					visibility: p.visibility,
+				})
+				.collect(),
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ws::mock::{MockResponse, mock_server};
+
+	#[test]
+	fn test_list_group_projects_follows_pages() {
+		let (base_url, server) = mock_server(vec![
+			MockResponse::json(
+				r#"[{"id":1,"path":"fw","path_with_namespace":"brocode/fw","ssh_url_to_repo":"git@gitlab.example.com:brocode/fw.git","http_url_to_repo":"https://gitlab.example.com/brocode/fw.git","archived":false}]"#,
+			)
+			.with_header("X-Next-Page", "2"),
+			MockResponse::json(
+				r#"[{"id":1,"path":"old","path_with_namespace":"brocode/sub/old","ssh_url_to_repo":"git@gitlab.example.com:brocode/sub/old.git","http_url_to_repo":"https://gitlab.example.com/brocode/sub/old.git","archived":true}]"#,
+			)
+			.with_header("X-Next-Page", ""),
+		]);
+		let api = gitlab_api(&format!("{base_url}/"), Some("secret")).unwrap();
+		let projects = api.list_group_projects("brocode/infra", false).unwrap();
+		let requests = server.join().unwrap();
+
+		assert_eq!(projects.len(), 1);
+		assert_eq!(projects[0].ssh_url_to_repo, "git@gitlab.example.com:brocode/fw.git");
+		assert!(requests[0].starts_with("GET /api/v4/groups/brocode%2Finfra/projects?include_subgroups=true&per_page=100&page=1 "));
+		assert!(requests[1].contains("page=2 "));
+		assert!(requests[0].to_lowercase().contains("private-token: secret"));
+	}
+	#[test]
+	fn test_list_group_projects_includes_archived() {
+		let (base_url, server) = mock_server(vec![MockResponse::json(
+			r#"[{"id":1,"path":"old","path_with_namespace":"brocode/old","ssh_url_to_repo":"git@gitlab.com:brocode/old.git","http_url_to_repo":"https://gitlab.com/brocode/old.git","archived":true}]"#,
+		)]);
+		let projects = gitlab_api(&base_url, None).unwrap().list_group_projects("brocode", true).unwrap();
+		server.join().unwrap();
+		assert_eq!(projects.len(), 1);
+	}
+	#[test]
+	fn test_subgroup_projects_are_named_by_their_path() {
+		assert_eq!(project_name("brocode/infra", "brocode/infra/fw"), "fw");
+		assert_eq!(project_name("brocode/infra", "Brocode/Infra/sub/fw"), "sub-fw");
+		assert_eq!(project_name("brocode/infra", "elsewhere/fw"), "elsewhere-fw");
+	}
+}
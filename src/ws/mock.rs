@@ -0,0 +1,77 @@
+// A minimal HTTP server for testing the api clients without network access.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread;
+
+pub struct MockResponse {
+	pub status: u16,
+	pub headers: Vec<(&'static str, String)>,
+	pub body: String,
+}
+
+impl MockResponse {
+	pub fn json(body: &str) -> MockResponse {
+		MockResponse {
+			status: 200,
+			headers: vec![],
+			body: body.to_owned(),
+		}
+	}
+
+	pub fn with_status(mut self, status: u16) -> MockResponse {
+		self.status = status;
+		self
+	}
+
+	pub fn with_header(mut self, name: &'static str, value: &str) -> MockResponse {
+		self.headers.push((name, value.to_owned()));
+		self
+	}
+}
+
+/// Serves the given responses in order, one per connection. `{base_url}` in a body is replaced with the url of the
+/// server (for absolute next page links). Returns the base url and a handle that yields the received requests
+/// (request line, headers and body).
+pub fn mock_server(responses: Vec<MockResponse>) -> (String, thread::JoinHandle<Vec<String>>) {
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let base_url = format!("http://{}", listener.local_addr().unwrap());
+	let server_url = base_url.clone();
+	let handle = thread::spawn(move || {
+		let mut requests = Vec::new();
+		for response in responses {
+			let (mut stream, _) = listener.accept().unwrap();
+			let mut reader = BufReader::new(stream.try_clone().unwrap());
+			let mut lines = Vec::new();
+			loop {
+				let mut line = String::new();
+				reader.read_line(&mut line).unwrap();
+				if line.trim().is_empty() {
+					break;
+				}
+				lines.push(line.trim().to_owned());
+			}
+			let content_length = lines
+				.iter()
+				.find_map(|l| l.to_lowercase().strip_prefix("content-length:").map(|len| len.trim().parse::<usize>().unwrap()))
+				.unwrap_or_default();
+			let mut body = vec![0; content_length];
+			reader.read_exact(&mut body).unwrap();
+			lines.push(String::from_utf8(body).unwrap());
+			requests.push(lines.join("\n"));
+			let body = response.body.replace("{base_url}", &server_url);
+			let headers: String = response.headers.iter().map(|(name, value)| format!("{name}: {value}\r\n")).collect();
+			write!(
+				stream,
+				"HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+				response.status,
+				headers,
+				body.len(),
+				body
+			)
+			.unwrap();
+		}
+		requests
+	});
+	(base_url, handle)
+}
@@ -1 +1,246 @@
+pub mod bitbucket;
+pub mod gitea;
 pub mod github;
+pub mod gitlab;
+
+#[cfg(test)]
+mod mock;
+
+use crate::config::settings::Settings;
+use crate::errors::AppError;
+use github::RepositorySource;
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+/// A repository as listed by a hosting provider.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RemoteRepository {
+	/// Stable id at the provider that does not change when the repository is renamed
+	pub id: Option<String>,
+	pub name: String,
+	/// ssh clone url
+	pub git: String,
+	pub https_git: Option<String>,
+	pub archived: bool,
+	pub topics: Vec<String>,
+	pub language: Option<String>,
+	/// public, private or internal
+	pub visibility: Option<String>,
+}
+
+impl RemoteRepository {
+	/// The https clone url if asked for and known, the ssh clone url otherwise.
+	pub fn clone_url(&self, https: bool) -> &str {
+		match self.https_git {
+			Some(ref https_git) if https => https_git,
+			_ => &self.git,
+		}
+	}
+}
+
+/// Restricts an import to repositories with one of the topics, one of the languages and the visibility. Empty means no restriction.
+#[derive(Debug, Clone, Default)]
+pub struct RepositoryFilter {
+	pub topics: BTreeSet<String>,
+	pub languages: BTreeSet<String>,
+	pub visibility: Option<String>,
+}
+
+impl RepositoryFilter {
+	pub fn matches(&self, repository: &RemoteRepository) -> bool {
+		let topic_matches = self.topics.is_empty() || repository.topics.iter().any(|t| self.topics.contains(t));
+		let language_matches = self.languages.is_empty()
+			|| repository
+				.language
+				.as_ref()
+				.is_some_and(|l| self.languages.iter().any(|wanted| wanted.eq_ignore_ascii_case(l)));
+		let visibility_matches = self
+			.visibility
+			.as_ref()
+			.is_none_or(|wanted| repository.visibility.as_ref().is_some_and(|v| wanted.eq_ignore_ascii_case(v)));
+		topic_matches && language_matches && visibility_matches
+	}
+}
+
+/// Percent-encodes everything but the unreserved characters so that an owner from the command line is exactly one
+/// segment of an api url.
+fn encode_path_segment(segment: &str) -> String {
+	segment
+		.bytes()
+		.map(|b| match b {
+			b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+			_ => format!("%{b:02X}"),
+		})
+		.collect()
+}
+
+/// A git hosting service that can list the repositories of an organization (or group, workspace, project).
+pub trait RepositoryProvider {
+	fn list_repositories(&mut self, owner: &str, include_archived: bool) -> Result<Vec<RemoteRepository>, AppError>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+	Github,
+	Gitlab,
+	Gitea,
+	Bitbucket,
+	BitbucketServer,
+}
+
+impl FromStr for Provider {
+	type Err = AppError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"github" => Ok(Provider::Github),
+			"gitlab" => Ok(Provider::Gitlab),
+			"gitea" | "forgejo" => Ok(Provider::Gitea),
+			"bitbucket" => Ok(Provider::Bitbucket),
+			"bitbucket-server" => Ok(Provider::BitbucketServer),
+			other => Err(AppError::UserError(format!(
+				"Unknown provider {other}. Use github, gitlab, gitea, forgejo, bitbucket or bitbucket-server."
+			))),
+		}
+	}
+}
+
+/// Creates the api client for the provider from the base url and token in the settings.
+pub fn repository_provider(settings: &Settings, provider: Provider) -> Result<Box<dyn RepositoryProvider>, AppError> {
+	match provider {
+		Provider::Github => Ok(Box::new(github_api_from_settings(settings)?)),
+		Provider::Gitlab => Ok(Box::new(gitlab::gitlab_api(
+			&settings.gitlab_url_or_default(),
+			settings.resolve_gitlab_token().as_deref(),
+		)?)),
+		Provider::Gitea => {
+			let base_url = settings
+				.gitea_url
+				.clone()
+				.ok_or_else(|| AppError::UserError("Can't call Gitea API because no gitea_url is specified in the configuration.".to_owned()))?;
+			Ok(Box::new(gitea::gitea_api(&base_url, settings.resolve_gitea_token().as_deref())?))
+		}
+		Provider::Bitbucket => Ok(Box::new(bitbucket::bitbucket_cloud_api(
+			&settings.bitbucket_url.clone().unwrap_or_else(|| "https://api.bitbucket.org".to_owned()),
+			settings.resolve_bitbucket_token().as_deref(),
+		)?)),
+		Provider::BitbucketServer => {
+			let base_url = settings
+				.bitbucket_url
+				.clone()
+				.ok_or_else(|| AppError::UserError("Can't call Bitbucket Server API because no bitbucket_url is specified in the configuration.".to_owned()))?;
+			Ok(Box::new(bitbucket::bitbucket_server_api(
+				&base_url,
+				settings.resolve_bitbucket_token().as_deref(),
+			)?))
+		}
+	}
+}
+
+fn github_api_from_settings(settings: &Settings) -> Result<github::GithubApi, AppError> {
+	let token = settings.resolve_github_token()?.ok_or_else(|| {
+		AppError::UserError(
+			"Can't call GitHub API because no github oauth token (settings.github_token or settings.github_token_command) specified in the configuration.".to_owned(),
+		)
+	})?;
+	github::github_api(&settings.github_api_url_or_default(), &token, settings.github_ssh_host.as_deref())
+}
+
+/// Forks the github repository `owner/name` into the account of the token owner or the organization.
+pub fn fork_repository(settings: &Settings, owner: &str, name: &str, organization: Option<&str>) -> Result<RemoteRepository, AppError> {
+	github_api_from_settings(settings)?.create_fork(owner, name, organization)
+}
+
+/// The hosts of the github clone urls: github.com or the GitHub Enterprise host and the configured ssh host.
+pub fn github_hosts(settings: &Settings) -> BTreeSet<String> {
+	let api_url = settings.github_api_url_or_default();
+	let api_host = api_url
+		.split_once("://")
+		.and_then(|(_, rest)| rest.split(['/', ':']).next())
+		.unwrap_or_default();
+	let mut hosts = BTreeSet::new();
+	hosts.insert(if api_host == "api.github.com" { "github.com" } else { api_host }.to_owned());
+	hosts.extend(settings.github_ssh_host.clone());
+	hosts
+}
+
+/// Runs one search for open pull requests on github per entry of search qualifiers, see [`github::GithubApi::search_pull_requests`].
+pub fn search_pull_requests(settings: &Settings, searches: &[String]) -> Result<Vec<github::PullRequest>, AppError> {
+	let api = github_api_from_settings(settings)?;
+	let mut pull_requests = Vec::new();
+	for qualifiers in searches {
+		pull_requests.extend(api.search_pull_requests(qualifiers)?);
+	}
+	Ok(pull_requests)
+}
+
+/// Creates an empty github repository in the account of the token owner or the organization.
+pub fn create_repository(
+	settings: &Settings,
+	organization: Option<&str>,
+	name: &str,
+	private: bool,
+	description: Option<&str>,
+) -> Result<RemoteRepository, AppError> {
+	github_api_from_settings(settings)?.create_repository(organization, name, private, description)
+}
+
+/// Lists the repositories of the source. Users, stars and teams are only supported on GitHub.
+pub fn list_repositories(
+	settings: &Settings,
+	provider: Provider,
+	source: &RepositorySource,
+	include_archived: bool,
+) -> Result<Vec<RemoteRepository>, AppError> {
+	match source {
+		RepositorySource::Organization(owner) => repository_provider(settings, provider)?.list_repositories(owner, include_archived),
+		_ if provider == Provider::Github => github_api_from_settings(settings)?.list_source_repositories(source, include_archived),
+		_ => Err(AppError::UserError("Importing users, stars and teams is only supported for github".to_owned())),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use maplit::btreeset;
+
+	fn repository() -> RemoteRepository {
+		RemoteRepository {
+			id: None,
+			name: "fw".to_owned(),
+			git: "git@github.com:brocode/fw.git".to_owned(),
+			https_git: None,
+			archived: false,
+			topics: vec!["cli".to_owned(), "workspace".to_owned()],
+			language: Some("Rust".to_owned()),
+			visibility: Some("public".to_owned()),
+		}
+	}
+
+	#[test]
+	fn test_empty_filter_matches_everything() {
+		assert!(RepositoryFilter::default().matches(&RemoteRepository::default()));
+	}
+	#[test]
+	fn test_filter_by_topic_and_language() {
+		let filter = RepositoryFilter {
+			topics: btreeset!["cli".to_owned(), "web".to_owned()],
+			languages: btreeset!["rust".to_owned()],
+			visibility: None,
+		};
+		assert!(filter.matches(&repository()));
+		assert!(!filter.matches(&RemoteRepository {
+			language: Some("Go".to_owned()),
+			..repository()
+		}));
+	}
+	#[test]
+	fn test_filter_by_visibility() {
+		let filter = RepositoryFilter {
+			visibility: Some("private".to_owned()),
+			..RepositoryFilter::default()
+		};
+		assert!(!filter.matches(&repository()));
+		assert!(!filter.matches(&RemoteRepository::default()));
+	}
+}